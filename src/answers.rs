@@ -0,0 +1,249 @@
+use crate::error::SolveError;
+use crate::input::normalise;
+use crate::solution::{Answer, Day};
+use std::path::Path;
+
+/// Where `answers record` and `answers verify` keep the answers by default,
+/// next to the inputs they belong to.
+pub const DEFAULT_PATH: &str = "input/2024/answers.tsv";
+
+/// FNV-1a of the normalised input, stable across platforms and releases
+/// unlike `DefaultHasher`.
+pub fn hash_input(input: &str) -> u64 {
+    normalise(input)
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// The answer a part gave for one input, one line per answer:
+/// `day part input_hash answer`, separated by tabs, the hash in hex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownAnswer {
+    pub day: u8,
+    pub part: u8,
+    pub input_hash: u64,
+    pub answer: Answer,
+}
+
+pub fn to_answers(answers: &[KnownAnswer]) -> String {
+    answers
+        .iter()
+        .map(|known| {
+            format!(
+                "{}\t{}\t{:016x}\t{}\n",
+                known.day, known.part, known.input_hash, known.answer
+            )
+        })
+        .collect()
+}
+
+pub fn parse_answers(answers: &str) -> Result<Vec<KnownAnswer>, String> {
+    answers
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_index, line)| {
+            let invalid = || format!("answers line {}: invalid entry {line:?}", line_index + 1);
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let [day, part, input_hash, answer] = fields.as_slice() else {
+                return Err(invalid());
+            };
+            Ok(KnownAnswer {
+                day: day.parse().map_err(|_| invalid())?,
+                part: part.parse().map_err(|_| invalid())?,
+                input_hash: u64::from_str_radix(input_hash, 16).map_err(|_| invalid())?,
+                answer: answer.parse().map_err(|_| invalid())?,
+            })
+        })
+        .collect()
+}
+
+pub fn save_answers(path: &Path, answers: &[KnownAnswer]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, to_answers(answers))
+}
+
+/// A missing file is no different from one without answers.
+pub fn load_answers(path: &Path) -> Result<Vec<KnownAnswer>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(answers) => parse_answers(&answers),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(format!("{}: {error}", path.display())),
+    }
+}
+
+/// Runs the day module's `partN` export of each of `parts` (`Day::part`) on
+/// `input` and stores the answers, replacing those recorded earlier for the
+/// same input.
+pub fn record(
+    answers: &mut Vec<KnownAnswer>,
+    day: &Day,
+    parts: &[u8],
+    input: &str,
+) -> Result<(), SolveError> {
+    let input_hash = hash_input(input);
+    for &part in parts {
+        let Some(solve) = day.part(part) else {
+            continue;
+        };
+        let known = KnownAnswer {
+            day: day.day,
+            part,
+            input_hash,
+            answer: solve(input)?,
+        };
+        match answers.iter_mut().find(|entry| {
+            (entry.day, entry.part, entry.input_hash) == (known.day, known.part, input_hash)
+        }) {
+            Some(entry) => *entry = known,
+            None => answers.push(known),
+        }
+    }
+    answers.sort_by_key(|known| (known.day, known.part, known.input_hash));
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Check {
+    pub day: u8,
+    pub part: u8,
+    /// `None` if nothing was recorded for this input, which fails the check
+    pub expected: Option<Answer>,
+    pub actual: Answer,
+}
+
+impl Check {
+    pub fn is_mismatch(&self) -> bool {
+        self.expected
+            .is_some_and(|expected| expected != self.actual)
+    }
+
+    pub fn is_missing(&self) -> bool {
+        self.expected.is_none()
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.expected == Some(self.actual)
+    }
+}
+
+/// Re-runs the day module's `partN` export of each of `parts` (`Day::part`)
+/// on `input` and compares with the answers recorded for exactly this input.
+pub fn verify(
+    answers: &[KnownAnswer],
+    day: &Day,
+    parts: &[u8],
+    input: &str,
+) -> Result<Vec<Check>, SolveError> {
+    let input_hash = hash_input(input);
+    parts
+        .iter()
+        .filter_map(|&part| {
+            let actual = day.part(part)?(input);
+            Some(actual.map(|actual| {
+                Check {
+                    day: day.day,
+                    part,
+                    expected: answers
+                        .iter()
+                        .find(|known| {
+                            (known.day, known.part, known.input_hash) == (day.day, part, input_hash)
+                        })
+                        .map(|known| known.answer),
+                    actual,
+                }
+            }))
+        })
+        .collect()
+}
+
+pub fn render_checks(checks: &[Check]) -> String {
+    checks
+        .iter()
+        .map(|check| match check.expected {
+            None => format!(
+                "day {} part {}: {} MISSING, no known answer for this input",
+                check.day, check.part, check.actual
+            ),
+            Some(expected) if expected == check.actual => {
+                format!("day {} part {}: {} ok", check.day, check.part, check.actual)
+            }
+            Some(expected) => format!(
+                "day {} part {}: MISMATCH, expected {expected}, got {}",
+                check.day, check.part, check.actual
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{hash_input, parse_answers, record, to_answers, verify, Check, KnownAnswer};
+    use crate::day1;
+    use crate::solution::{find_day, Day};
+
+    #[test]
+    fn test_hash_input() {
+        assert_eq!(hash_input(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash_input("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(hash_input("3   4"), hash_input("4   3"));
+        assert_eq!(hash_input("3   4\r\n4   3\r\n"), hash_input("3   4\n4   3"));
+    }
+
+    #[test]
+    fn test_answers_file() {
+        let answers = [KnownAnswer {
+            day: 9,
+            part: 2,
+            input_hash: 0xabc,
+            answer: 2858,
+        }];
+        let file = to_answers(&answers);
+        assert_eq!(file, "9\t2\t0000000000000abc\t2858\n");
+        assert_eq!(parse_answers(&file), Ok(answers.to_vec()));
+        assert!(parse_answers("9\t2\tnothex\t2858").is_err());
+        assert!(parse_answers("9\t2\t2858").is_err());
+    }
+
+    #[test]
+    fn test_record_and_verify() {
+        let sample = "3   4\n4   3\n2   5\n1   3\n3   9\n3   3";
+        let day1 = find_day(1).unwrap();
+        let mut answers = Vec::new();
+        record(&mut answers, day1, &[1, 2], sample).unwrap();
+        record(&mut answers, day1, &[2], sample).unwrap();
+        assert_eq!(
+            answers.iter().map(|known| known.answer).collect::<Vec<_>>(),
+            vec![11, 31]
+        );
+        assert_eq!(answers[0].answer, day1::part1(sample).unwrap());
+        assert!(verify(&answers, day1, &[1, 2], sample)
+            .unwrap()
+            .iter()
+            .all(|check| check.is_ok()));
+
+        // a refactor gone wrong
+        let broken = Day {
+            part2: |_| Ok(30),
+            ..*day1
+        };
+        assert_eq!(
+            verify(&answers, &broken, &[1, 2], sample).unwrap()[1],
+            Check {
+                day: 1,
+                part: 2,
+                expected: Some(31),
+                actual: 30
+            }
+        );
+        let other = verify(&answers, day1, &[1], "1   1").unwrap();
+        assert_eq!(other[0].expected, None);
+        assert!(!other[0].is_mismatch());
+        assert!(other[0].is_missing() && !other[0].is_ok());
+    }
+}
@@ -0,0 +1,341 @@
+use crate::error::SolveError;
+use crate::solution::{Answer, Day, Variant};
+use std::hint::black_box;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BenchOptions {
+    /// untimed runs before measuring
+    pub warmup: u32,
+    pub runs: u32,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions {
+            warmup: 3,
+            runs: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+}
+
+impl Stats {
+    pub fn new(mut samples: Vec<Duration>) -> Self {
+        assert!(!samples.is_empty(), "need at least one sample");
+        samples.sort_unstable();
+        // nearest rank
+        let rank = |percentile: usize| (samples.len() * percentile).div_ceil(100).max(1) - 1;
+        Stats {
+            min: samples[0],
+            median: samples[rank(50)],
+            p95: samples[rank(95)],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Measurement {
+    pub day: u8,
+    pub part: u8,
+    pub variant: &'static str,
+    pub answer: Answer,
+    pub parse: Stats,
+    pub solve: Stats,
+}
+
+impl Measurement {
+    pub fn median(&self) -> Duration {
+        self.parse.median + self.solve.median
+    }
+}
+
+pub fn bench_variant(
+    day: u8,
+    variant: &Variant,
+    input: &str,
+    options: BenchOptions,
+) -> Result<Measurement, SolveError> {
+    for _ in 0..options.warmup {
+        black_box(variant.run(black_box(input))?);
+    }
+
+    let mut parse_samples = Vec::with_capacity(options.runs as usize);
+    let mut solve_samples = Vec::with_capacity(options.runs as usize);
+    let mut answer = 0;
+    for _ in 0..options.runs.max(1) {
+        let start = Instant::now();
+        let parsed = (variant.parse)(black_box(input))?;
+        parse_samples.push(start.elapsed());
+
+        let start = Instant::now();
+        answer = black_box((variant.solve)(&parsed))?;
+        solve_samples.push(start.elapsed());
+    }
+
+    Ok(Measurement {
+        day,
+        part: variant.part,
+        variant: variant.name,
+        answer,
+        parse: Stats::new(parse_samples),
+        solve: Stats::new(solve_samples),
+    })
+}
+
+/// Benchmarks every variant of `day`, optionally only of one part or with one
+/// name.
+pub fn bench_day(
+    day: &Day,
+    part: Option<u8>,
+    name: Option<&str>,
+    input: &str,
+    options: BenchOptions,
+) -> Result<Vec<Measurement>, SolveError> {
+    day.variants
+        .iter()
+        .filter(|variant| part.is_none_or(|part| variant.part == part))
+        .filter(|variant| name.is_none_or(|name| variant.name.eq_ignore_ascii_case(name)))
+        .map(|variant| bench_variant(day.day, variant, input, options))
+        .collect()
+}
+
+pub(crate) fn format_duration(duration: Duration) -> String {
+    match duration.as_nanos() {
+        0..1_000 => format!("{}ns", duration.as_nanos()),
+        1_000..1_000_000 => format!("{:.1}µs", duration.as_nanos() as f64 / 1e3),
+        1_000_000..1_000_000_000 => format!("{:.2}ms", duration.as_nanos() as f64 / 1e6),
+        _ => format!("{:.3}s", duration.as_secs_f64()),
+    }
+}
+
+pub fn render(measurements: &[Measurement]) -> String {
+    let mut lines = vec![format!(
+        "{:<8}{:<20}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}  answer",
+        "part", "variant", "parse min", "median", "p95", "solve min", "median", "p95"
+    )];
+    for measurement in measurements {
+        lines.push(format!(
+            "{:<8}{:<20}{:>12}{:>12}{:>12}{:>12}{:>12}{:>12}  {}",
+            format!("{}/{}", measurement.day, measurement.part),
+            measurement.variant,
+            format_duration(measurement.parse.min),
+            format_duration(measurement.parse.median),
+            format_duration(measurement.parse.p95),
+            format_duration(measurement.solve.min),
+            format_duration(measurement.solve.median),
+            format_duration(measurement.solve.p95),
+            measurement.answer
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Median timings of an earlier run, one line per variant:
+/// `day part variant parse_ns solve_ns`, separated by tabs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BaselineEntry {
+    pub day: u8,
+    pub part: u8,
+    pub variant: String,
+    pub parse: Duration,
+    pub solve: Duration,
+}
+
+pub fn to_baseline(measurements: &[Measurement]) -> String {
+    measurements
+        .iter()
+        .map(|measurement| {
+            format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                measurement.day,
+                measurement.part,
+                measurement.variant,
+                measurement.parse.median.as_nanos(),
+                measurement.solve.median.as_nanos()
+            )
+        })
+        .collect()
+}
+
+pub fn parse_baseline(baseline: &str) -> Result<Vec<BaselineEntry>, String> {
+    baseline
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_index, line)| {
+            let invalid = || format!("baseline line {}: invalid entry {line:?}", line_index + 1);
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let [day, part, variant, parse, solve] = fields.as_slice() else {
+                return Err(invalid());
+            };
+            let nanos = |field: &str| field.parse().map(Duration::from_nanos);
+            Ok(BaselineEntry {
+                day: day.parse().map_err(|_| invalid())?,
+                part: part.parse().map_err(|_| invalid())?,
+                variant: variant.to_string(),
+                parse: nanos(parse).map_err(|_| invalid())?,
+                solve: nanos(solve).map_err(|_| invalid())?,
+            })
+        })
+        .collect()
+}
+
+pub fn save_baseline(path: &Path, measurements: &[Measurement]) -> std::io::Result<()> {
+    std::fs::write(path, to_baseline(measurements))
+}
+
+pub fn load_baseline(path: &Path) -> Result<Vec<BaselineEntry>, String> {
+    let baseline =
+        std::fs::read_to_string(path).map_err(|error| format!("{}: {error}", path.display()))?;
+    parse_baseline(&baseline)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regression {
+    pub day: u8,
+    pub part: u8,
+    pub variant: &'static str,
+    pub baseline: Duration,
+    pub current: Duration,
+}
+
+impl Regression {
+    pub fn slowdown(&self) -> f64 {
+        self.current.as_secs_f64() / self.baseline.as_secs_f64()
+    }
+}
+
+/// Variants whose median parse + solve time grew by more than `tolerance`
+/// (0.1 for 10%) compared to the baseline. Variants missing from the
+/// baseline are ignored.
+pub fn find_regressions(
+    measurements: &[Measurement],
+    baseline: &[BaselineEntry],
+    tolerance: f64,
+) -> Vec<Regression> {
+    measurements
+        .iter()
+        .filter_map(|measurement| {
+            let entry = baseline.iter().find(|entry| {
+                entry.day == measurement.day
+                    && entry.part == measurement.part
+                    && entry.variant == measurement.variant
+            })?;
+            let baseline = entry.parse + entry.solve;
+            let current = measurement.median();
+            (current.as_secs_f64() > baseline.as_secs_f64() * (1.0 + tolerance)).then_some(
+                Regression {
+                    day: measurement.day,
+                    part: measurement.part,
+                    variant: measurement.variant,
+                    baseline,
+                    current,
+                },
+            )
+        })
+        .collect()
+}
+
+pub fn render_regressions(regressions: &[Regression]) -> String {
+    regressions
+        .iter()
+        .map(|regression| {
+            format!(
+                "regression: day {} part {} ({}) {} -> {} ({:.0}% slower)",
+                regression.day,
+                regression.part,
+                regression.variant,
+                format_duration(regression.baseline),
+                format_duration(regression.current),
+                (regression.slowdown() - 1.0) * 100.0
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        bench_day, find_regressions, parse_baseline, to_baseline, BenchOptions, Measurement, Stats,
+    };
+    use crate::solution::find_day;
+    use std::time::Duration;
+
+    fn ms(samples: &[u64]) -> Vec<Duration> {
+        samples
+            .iter()
+            .map(|ms| Duration::from_millis(*ms))
+            .collect()
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::new(ms(&[5, 1, 4, 2, 3, 100, 6, 7, 8, 9]));
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.median, Duration::from_millis(5));
+        assert_eq!(stats.p95, Duration::from_millis(100));
+        let stats = Stats::new(ms(&[3]));
+        assert_eq!(
+            (stats.min, stats.median, stats.p95),
+            (stats.min, stats.min, stats.min)
+        );
+    }
+
+    #[test]
+    fn test_bench_day() {
+        let options = BenchOptions { warmup: 1, runs: 3 };
+        let sample = "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9";
+        let day2 = find_day(2).unwrap();
+        let measurements = bench_day(day2, Some(1), None, sample, options).unwrap();
+        assert_eq!(measurements.len(), day2.variants_of(1).count());
+        assert!(measurements
+            .iter()
+            .all(|measurement| measurement.answer == 2));
+
+        let measurements = bench_day(day2, None, Some("delta"), sample, options).unwrap();
+        assert_eq!(
+            measurements.iter().map(|m| m.answer).collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+    }
+
+    #[test]
+    fn test_baseline() {
+        let measurement = |variant, median| Measurement {
+            day: 5,
+            part: 1,
+            variant,
+            answer: 143,
+            parse: Stats::new(ms(&[1])),
+            solve: Stats::new(ms(&[median])),
+        };
+        let old = [
+            measurement("CheckRules", 10),
+            measurement("CheckSorted", 10),
+        ];
+        let baseline = parse_baseline(&to_baseline(&old)).unwrap();
+        assert_eq!(baseline.len(), 2);
+        assert_eq!(baseline[1].variant, "CheckSorted");
+        assert_eq!(baseline[1].solve, Duration::from_millis(10));
+
+        let new = [
+            measurement("CheckRules", 11),
+            measurement("CheckSorted", 20),
+            measurement("Direct", 50),
+        ];
+        let regressions = find_regressions(&new, &baseline, 0.1);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].variant, "CheckSorted");
+
+        assert!(parse_baseline("5\t1\tCheckRules\t10").is_err());
+    }
+}
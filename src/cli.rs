@@ -0,0 +1,667 @@
+use crate::answers::{self, KnownAnswer};
+use crate::bench::{self, BenchOptions, Regression};
+use crate::profile;
+use crate::solution::{find_day, Answer, Day, DAYS};
+use std::fmt::Write as _;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str =
+    "usage: advent_of_code_2024 <day> [<part>] [--variant <name>] [--input <path>|-] [--json]
+       advent_of_code_2024 bench <day> [<part>] [--variant <name>] [--input <path>|-]
+                           [--runs <n>] [--warmup <n>] [--save-baseline <path>]
+                           [--baseline <path>] [--tolerance <percent>]
+       advent_of_code_2024 profile <day> [<part>] [--variant <name>] [--input <path>|-]
+       advent_of_code_2024 answers record|verify [<day> [<part>]] [--input <path>|-]
+                           [--answers <path>]
+
+  <day>             1 to 25, only implemented days can be run
+  <part>            1 or 2, both parts if omitted
+  --variant <name>  run a named #[aoc] variant, e.g. Delta or Parsed
+  --input <path>    read the puzzle input from <path>, or stdin for -
+                    (defaults to input/2024/day<day>.txt)
+  --json            print the answers as JSON
+
+  bench             time every variant (or only --variant) of the day
+  --runs <n>        timed runs per variant (20)
+  --warmup <n>      untimed runs before that (3)
+  --save-baseline   write the median timings to <path>
+  --baseline        compare against timings saved earlier, failing when a
+                    variant got slower by more than --tolerance (10%)
+
+  profile           wall time, allocations and peak heap usage of parsing
+                    and solving with every variant (or only --variant),
+                    allocations need --features count-allocations
+
+  answers record    store the answers for the input, or for the inputs of
+                    every day if no day is given
+  answers verify    re-run the parts and compare with the stored answers,
+                    failing on any mismatch or missing answer
+  --answers <path>  the answers file (input/2024/answers.tsv)";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    /// `input/2024/day<day>.txt`, where aoc-runner keeps the inputs
+    Default,
+    Path(PathBuf),
+    Stdin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Plain,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Run,
+    Bench {
+        options: BenchOptions,
+        save_baseline: Option<PathBuf>,
+        baseline: Option<PathBuf>,
+        tolerance_percent: u32,
+    },
+    Profile,
+    Answers {
+        /// verify instead of record
+        verify: bool,
+        path: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Args {
+    pub command: Command,
+    /// only optional for `answers`
+    pub day: Option<u8>,
+    pub part: Option<u8>,
+    pub variant: Option<String>,
+    pub input: InputSource,
+    pub format: Format,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub day: u8,
+    pub part: u8,
+    pub variant: Option<&'static str>,
+    pub answer: Answer,
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut args = args.into_iter().peekable();
+    let mut command = match args.peek().map(String::as_str) {
+        Some("bench") => {
+            args.next();
+            Command::Bench {
+                options: BenchOptions::default(),
+                save_baseline: None,
+                baseline: None,
+                tolerance_percent: 10,
+            }
+        }
+        Some("profile") => {
+            args.next();
+            Command::Profile
+        }
+        Some("answers") => {
+            args.next();
+            let verify = match args.next().as_deref() {
+                Some("record") => false,
+                Some("verify") => true,
+                _ => return Err("answers needs record or verify".to_string()),
+            };
+            Command::Answers {
+                verify,
+                path: PathBuf::from(answers::DEFAULT_PATH),
+            }
+        }
+        _ => Command::Run,
+    };
+    let mut positional = Vec::new();
+    let mut variant = None;
+    let mut input = InputSource::Default;
+    let mut format = Format::Plain;
+
+    while let Some(arg) = args.next() {
+        let mut number = |name: &str| {
+            let value = args.next().ok_or(format!("{name} needs a number"))?;
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("invalid number {value} for {name}"))
+        };
+        match (arg.as_str(), &mut command) {
+            ("--variant" | "-v", _) => {
+                variant = Some(args.next().ok_or("--variant needs a name")?);
+            }
+            ("--input" | "-i", _) => {
+                input = match args.next().ok_or("--input needs a path")?.as_str() {
+                    "-" => InputSource::Stdin,
+                    path => InputSource::Path(PathBuf::from(path)),
+                }
+            }
+            ("--json", _) => format = Format::Json,
+            ("--runs", Command::Bench { options, .. }) => options.runs = number("--runs")?.max(1),
+            ("--warmup", Command::Bench { options, .. }) => options.warmup = number("--warmup")?,
+            (
+                "--tolerance",
+                Command::Bench {
+                    tolerance_percent, ..
+                },
+            ) => *tolerance_percent = number("--tolerance")?,
+            ("--save-baseline", Command::Bench { save_baseline, .. }) => {
+                *save_baseline = Some(args.next().ok_or("--save-baseline needs a path")?.into())
+            }
+            ("--baseline", Command::Bench { baseline, .. }) => {
+                *baseline = Some(args.next().ok_or("--baseline needs a path")?.into())
+            }
+            ("--answers", Command::Answers { path, .. }) => {
+                *path = args.next().ok_or("--answers needs a path")?.into()
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option {arg}")),
+            _ => positional.push(arg),
+        }
+    }
+
+    let (day, part) = match positional.as_slice() {
+        [] if matches!(command, Command::Answers { .. }) => (None, None),
+        [] => return Err("missing day".to_string()),
+        [day] => (Some(day), None),
+        [day, part] => (Some(day), Some(part)),
+        _ => return Err(format!("unexpected argument {}", positional[2])),
+    };
+    let day = day
+        .map(|day| {
+            day.trim_start_matches("day")
+                .parse::<u8>()
+                .map_err(|_| format!("invalid day {day}"))
+        })
+        .transpose()?;
+    if day.is_none() && input != InputSource::Default {
+        return Err("--input needs a day".to_string());
+    }
+    let part = part
+        .map(|part| match part.trim_start_matches("part") {
+            "1" => Ok(1),
+            "2" => Ok(2),
+            _ => Err(format!("invalid part {part}, expected 1 or 2")),
+        })
+        .transpose()?;
+
+    Ok(Args {
+        command,
+        day,
+        part,
+        variant,
+        input,
+        format,
+    })
+}
+
+pub fn read_input(source: &InputSource, day: u8) -> Result<String, String> {
+    let input = match source {
+        InputSource::Default => {
+            let path = format!("input/2024/day{day}.txt");
+            std::fs::read_to_string(&path).map_err(|error| format!("{path}: {error}"))?
+        }
+        InputSource::Path(path) => {
+            std::fs::read_to_string(path).map_err(|error| format!("{}: {error}", path.display()))?
+        }
+        InputSource::Stdin => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|error| format!("stdin: {error}"))?;
+            input
+        }
+    };
+    // same as aoc-runner
+    Ok(input.trim_end_matches('\n').to_string())
+}
+
+fn run_part(day: &Day, part: u8, variant: Option<&str>, input: &str) -> Result<Outcome, String> {
+    let (variant, answer) = match variant {
+        None => {
+            let solve = day.part(part).ok_or("invalid part")?;
+            (None, solve(input).map_err(|error| error.to_string())?)
+        }
+        Some(name) => {
+            let variant = day.variant(part, name).ok_or_else(|| {
+                format!(
+                    "day {} part {part} has no variant {name}, available: {}",
+                    day.day,
+                    day.variants_of(part)
+                        .map(|variant| variant.name)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+            let answer = variant.run(input).map_err(|error| error.to_string())?;
+            (Some(variant.name), answer)
+        }
+    };
+    Ok(Outcome {
+        day: day.day,
+        part,
+        variant,
+        answer,
+    })
+}
+
+fn solved_day(day: Option<u8>) -> Result<&'static Day, String> {
+    let day = day.ok_or("missing day")?;
+    find_day(day).ok_or_else(|| format!("day {day} is not solved yet"))
+}
+
+pub fn run(args: &Args, input: &str) -> Result<Vec<Outcome>, String> {
+    let day = solved_day(args.day)?;
+    let parts = match args.part {
+        Some(part) => vec![part],
+        // only the parts which know the variant when running both
+        None => (1..=2)
+            .filter(|part| {
+                args.variant
+                    .as_ref()
+                    .is_none_or(|name| day.variant(*part, name).is_some())
+            })
+            .collect(),
+    };
+    if parts.is_empty() {
+        // reuse the error listing the available variants
+        run_part(day, 1, args.variant.as_deref(), input)?;
+    }
+    parts
+        .into_iter()
+        .map(|part| run_part(day, part, args.variant.as_deref(), input))
+        .collect()
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for char in value.chars() {
+        match char {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            _ if char.is_control() => {
+                let _ = write!(out, "\\u{:04x}", char as u32);
+            }
+            _ => out.push(char),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn render(outcomes: &[Outcome], format: Format) -> String {
+    match format {
+        Format::Plain => outcomes
+            .iter()
+            .map(|outcome| match outcome.variant {
+                Some(variant) => format!(
+                    "day {} part {} ({variant}): {}",
+                    outcome.day, outcome.part, outcome.answer
+                ),
+                None => format!(
+                    "day {} part {}: {}",
+                    outcome.day, outcome.part, outcome.answer
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Format::Json => format!(
+            "[{}]",
+            outcomes
+                .iter()
+                .map(|outcome| format!(
+                    "{{\"day\":{},\"part\":{},\"variant\":{},\"answer\":{}}}",
+                    outcome.day,
+                    outcome.part,
+                    outcome
+                        .variant
+                        .map(json_string)
+                        .unwrap_or("null".to_string()),
+                    outcome.answer
+                ))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+    }
+}
+
+pub fn main(args: impl IntoIterator<Item = String>) -> ExitCode {
+    let args: Vec<String> = args.into_iter().collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    // the output, and what went wrong if the command should fail
+    let result = match &args.command {
+        Command::Answers { .. } => run_answers(&args),
+        _ => solved_day(args.day)
+            .and_then(|day| read_input(&args.input, day.day))
+            .and_then(|input| match &args.command {
+                Command::Bench { .. } => {
+                    let (output, regressions) = run_bench(&args, &input)?;
+                    Ok((
+                        output,
+                        (!regressions.is_empty()).then(|| bench::render_regressions(&regressions)),
+                    ))
+                }
+                Command::Profile => Ok((run_profile(&args, &input)?, None)),
+                _ => Ok((render(&run(&args, &input)?, args.format), None)),
+            }),
+    };
+    match result {
+        Ok((output, failure)) => {
+            println!("{output}");
+            match failure {
+                None => ExitCode::SUCCESS,
+                Some(failure) => {
+                    eprintln!("{failure}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Renders the timings, along with the regressions against the baseline.
+pub fn run_bench(args: &Args, input: &str) -> Result<(String, Vec<Regression>), String> {
+    let Command::Bench {
+        options,
+        save_baseline,
+        baseline,
+        tolerance_percent,
+    } = &args.command
+    else {
+        return Err("not a benchmark".to_string());
+    };
+    let day = solved_day(args.day)?;
+    let measurements = bench::bench_day(day, args.part, args.variant.as_deref(), input, *options)
+        .map_err(|error| error.to_string())?;
+    if measurements.is_empty() {
+        return Err(format!(
+            "day {} has no variant {}",
+            day.day,
+            args.variant.as_deref().unwrap_or_default()
+        ));
+    }
+
+    let mut output = bench::render(&measurements);
+    if let Some(path) = save_baseline {
+        bench::save_baseline(path, &measurements)
+            .map_err(|error| format!("{}: {error}", path.display()))?;
+    }
+    let mut regressions = Vec::new();
+    if let Some(path) = baseline {
+        regressions = bench::find_regressions(
+            &measurements,
+            &bench::load_baseline(path)?,
+            *tolerance_percent as f64 / 100.0,
+        );
+        if regressions.is_empty() {
+            output.push_str("\nno regressions");
+        }
+    }
+    Ok((output, regressions))
+}
+
+pub fn run_profile(args: &Args, input: &str) -> Result<String, String> {
+    let day = solved_day(args.day)?;
+    let profiles = profile::profile_day(day, args.part, args.variant.as_deref(), input)
+        .map_err(|error| error.to_string())?;
+    if profiles.is_empty() {
+        return Err(format!(
+            "day {} has no variant {}",
+            day.day,
+            args.variant.as_deref().unwrap_or_default()
+        ));
+    }
+    Ok(profile::render(&profiles, profile::counting_allocations()))
+}
+
+/// Records or verifies the answers for one day, or for every day with an
+/// input in `input/2024` when no day is given.
+pub fn run_answers(args: &Args) -> Result<(String, Option<String>), String> {
+    let Command::Answers { verify, path } = &args.command else {
+        return Err("not an answers command".to_string());
+    };
+    let days = match args.day {
+        Some(day) => vec![solved_day(Some(day))?],
+        None => DAYS.iter().collect(),
+    };
+    let parts = args.part.map_or(vec![1, 2], |part| vec![part]);
+    let mut known: Vec<KnownAnswer> = answers::load_answers(path)?;
+
+    let mut checks = Vec::new();
+    let mut skipped = Vec::new();
+    for day in days {
+        let input = match read_input(&args.input, day.day) {
+            Ok(input) if !input.is_empty() => input,
+            // only complain about the inputs which were asked for
+            Ok(_) if args.day.is_none() => continue,
+            Err(_) if args.day.is_none() => {
+                skipped.push(day.day);
+                continue;
+            }
+            Ok(_) => return Err(format!("the input for day {} is empty", day.day)),
+            Err(error) => return Err(error),
+        };
+        if !*verify {
+            answers::record(&mut known, day, &parts, &input).map_err(|error| error.to_string())?;
+        }
+        checks.extend(
+            answers::verify(&known, day, &parts, &input).map_err(|error| error.to_string())?,
+        );
+    }
+    if !*verify {
+        answers::save_answers(path, &known)
+            .map_err(|error| format!("{}: {error}", path.display()))?;
+    }
+
+    let mut output = answers::render_checks(&checks);
+    if checks.is_empty() {
+        output.push_str("no inputs to check");
+    }
+    if !skipped.is_empty() {
+        let _ = write!(output, "\nno input for day {skipped:?}");
+    }
+    let mismatches = checks.iter().filter(|check| check.is_mismatch()).count();
+    let missing = checks.iter().filter(|check| check.is_missing()).count();
+    let failure = if mismatches > 0 {
+        Some(format!("{mismatches} answers changed, {missing} missing"))
+    } else if missing > 0 {
+        Some(format!("{missing} answers missing, record them first"))
+    } else if checks.is_empty() && *verify {
+        Some("nothing was verified".to_string())
+    } else {
+        None
+    };
+    Ok((output, failure))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        parse_args, render, run, run_answers, Args, Command, Format, InputSource, Outcome,
+    };
+    use crate::bench::BenchOptions;
+
+    fn args(args: &str) -> Result<Args, String> {
+        parse_args(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            args("2 1 --variant Delta --input - --json"),
+            Ok(Args {
+                command: Command::Run,
+                day: Some(2),
+                part: Some(1),
+                variant: Some("Delta".to_string()),
+                input: InputSource::Stdin,
+                format: Format::Json,
+            })
+        );
+        assert_eq!(
+            args("day11").map(|args| (args.day, args.part)),
+            Ok((Some(11), None))
+        );
+        assert!(args("").is_err());
+        assert!(args("1 3").is_err());
+        assert!(args("1 --variant").is_err());
+        assert!(args("1 --fast").is_err());
+        assert!(args("1 --runs 5").is_err());
+    }
+
+    #[test]
+    fn test_parse_bench_args() {
+        let parsed = args("bench 5 2 --runs 50 --warmup 0 --baseline base.tsv").unwrap();
+        assert_eq!((parsed.day, parsed.part), (Some(5), Some(2)));
+        assert_eq!(
+            parsed.command,
+            Command::Bench {
+                options: BenchOptions {
+                    warmup: 0,
+                    runs: 50
+                },
+                save_baseline: None,
+                baseline: Some("base.tsv".into()),
+                tolerance_percent: 10,
+            }
+        );
+        assert!(args("bench 5 --runs many").is_err());
+        assert_eq!(
+            args("profile 6 2 --variant Direct").map(|args| args.command),
+            Ok(Command::Profile)
+        );
+        assert!(args("profile 6 --runs 3").is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let sample = "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9";
+        let outcomes = run(&args("2").unwrap(), sample).unwrap();
+        assert_eq!(
+            outcomes.iter().map(|o| o.answer).collect::<Vec<_>>(),
+            vec![2, 4]
+        );
+        let outcomes = run(&args("2 --variant naïve").unwrap(), sample).unwrap();
+        assert_eq!(
+            outcomes,
+            vec![Outcome {
+                day: 2,
+                part: 1,
+                variant: Some("Naïve"),
+                answer: 2
+            }]
+        );
+        assert!(run(&args("2 2 --variant naïve").unwrap(), sample)
+            .unwrap_err()
+            .contains("DirectIterator, Delta, Iterator"));
+        assert!(run(&args("2 --variant Foo").unwrap(), sample).is_err());
+        assert!(run(&args("25").unwrap(), sample).is_err());
+    }
+
+    #[test]
+    fn test_run_invalid_input() {
+        let error = run(&args("1").unwrap(), "3 4\n4 x").unwrap_err();
+        assert!(error.starts_with("day 1, line 2, column 3"), "{error}");
+        assert!(run(&args("1 --variant direct").unwrap(), "3 4\n4 x").is_err());
+    }
+
+    #[test]
+    fn test_answers() {
+        let parsed = args("answers verify").unwrap();
+        assert_eq!(parsed.day, None);
+        assert!(matches!(
+            parsed.command,
+            Command::Answers { verify: true, .. }
+        ));
+        assert!(args("answers").is_err());
+        assert!(args("answers record --input day1.txt").is_err());
+        assert!(args("1 --answers answers.tsv").is_err());
+
+        let dir = std::env::temp_dir().join(format!("aoc-answers-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("day1.txt");
+        let answers = dir.join("answers.tsv");
+        std::fs::write(&input, "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n").unwrap();
+        let command = |mode: &str| {
+            args(&format!(
+                "answers {mode} 1 --input {} --answers {}",
+                input.display(),
+                answers.display()
+            ))
+            .unwrap()
+        };
+
+        let (output, failure) = run_answers(&command("verify")).unwrap();
+        assert!(output.contains("MISSING, no known answer"), "{output}");
+        assert_eq!(
+            failure.as_deref(),
+            Some("2 answers missing, record them first")
+        );
+        run_answers(&command("record")).unwrap();
+        let (output, failure) = run_answers(&command("verify")).unwrap();
+        assert_eq!(output, "day 1 part 1: 11 ok\nday 1 part 2: 31 ok");
+        assert_eq!(failure, None);
+
+        // pretend an earlier version answered differently
+        let recorded_answers = std::fs::read_to_string(&answers).unwrap();
+        std::fs::write(&answers, recorded_answers.replace("\t31\n", "\t30\n")).unwrap();
+        let (output, failure) = run_answers(&command("verify")).unwrap();
+        assert!(output.contains("MISMATCH, expected 30, got 31"), "{output}");
+        assert!(failure.is_some());
+
+        // same input saved with Windows line endings
+        let recorded = std::fs::read_to_string(&input).unwrap();
+        std::fs::write(&input, recorded.replace('\n', "\r\n")).unwrap();
+        std::fs::write(&answers, recorded_answers).unwrap();
+        let (output, failure) = run_answers(&command("verify")).unwrap();
+        assert_eq!(output, "day 1 part 1: 11 ok\nday 1 part 2: 31 ok");
+        assert_eq!(failure, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_render() {
+        let outcomes = [
+            Outcome {
+                day: 1,
+                part: 1,
+                variant: None,
+                answer: 11,
+            },
+            Outcome {
+                day: 1,
+                part: 2,
+                variant: Some("Di\"rect"),
+                answer: 31,
+            },
+        ];
+        assert_eq!(
+            render(&outcomes, Format::Plain),
+            "day 1 part 1: 11\nday 1 part 2 (Di\"rect): 31"
+        );
+        assert_eq!(
+            render(&outcomes, Format::Json),
+            r#"[{"day":1,"part":1,"variant":null,"answer":11},{"day":1,"part":2,"variant":"Di\"rect","answer":31}]"#
+        );
+    }
+}
@@ -1,82 +1,799 @@
+use crate::error::{parse_number, ParseError, SolveError};
+use crate::input::normalise;
+use crate::solution::{
+    input, parse_input, raw_input, Answer, IntoAnswer, Solution, Variant, DEFAULT_VARIANT,
+};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
 #[aoc(day1, part1, Direct)]
-pub fn get_total_distance_direct(input: &str) -> u32 {
-    let (mut list_left, mut list_right) = input_generator(input);
+pub fn get_total_distance_direct(input: &str) -> Result<u64, ParseError> {
+    let (mut list_left, mut list_right) = try_input_generator(input)?;
 
     list_left.sort_unstable();
     list_right.sort_unstable();
 
-    list_left
+    Ok(list_left
         .into_iter()
         .zip(list_right)
-        .map(|(a, b)| a.abs_diff(b))
-        .sum()
+        .map(|(a, b)| a.abs_diff(b) as u64)
+        .sum())
 }
 
 #[aoc(day1, part2, Direct)]
-pub fn get_similarity_score_direct(input: &str) -> u32 {
-    let generated = input_generator(input);
-    get_similarity_score(&generated)
+pub fn get_similarity_score_direct(input: &str) -> Result<u64, ParseError> {
+    let generated = try_input_generator(input)?;
+    Ok(get_similarity_score(&generated))
 }
 
 #[aoc_generator(day1)]
 pub fn input_generator(input: &str) -> (Vec<u32>, Vec<u32>) {
-    let mut list_left = Vec::new();
-    let mut list_right = Vec::new();
+    try_input_generator(input).unwrap_or_else(|error| panic!("{error}"))
+}
+
+pub fn try_input_generator(input: &str) -> Result<(Vec<u32>, Vec<u32>), ParseError> {
+    let mut columns = try_read_columns(input, Separator::Whitespace, Some(2))?.columns;
+    let list_right = columns.pop().unwrap_or_default();
+    let list_left = columns.pop().unwrap_or_default();
+    Ok((list_left, list_right))
+}
+
+/// What separates the IDs on a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    /// any run of spaces and tabs, like the puzzle input
+    Whitespace,
+    /// a single character such as ',' or '\t', with optional spaces around it
+    Char(char),
+}
 
-    let parse = |s: &str| s.trim().parse::<u32>().unwrap();
-    for line in input.lines() {
-        let (left, right) = line.split_once(' ').unwrap();
+/// Location lists side by side, one column per list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocationColumns {
+    pub columns: Vec<Vec<u32>>,
+}
+
+impl LocationColumns {
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Columns `left` and `right` as a pair of lists, like the puzzle input.
+    /// `None` if either column does not exist.
+    pub fn pair(&self, left: usize, right: usize) -> Option<(&[u32], &[u32])> {
+        Some((self.columns.get(left)?, self.columns.get(right)?))
+    }
+
+    pub fn distance(&self, left: usize, right: usize) -> Option<u64> {
+        let (left, right) = self.pair(left, right)?;
+        Some(sorted_distance(&sorted(left), &sorted(right)))
+    }
 
-        list_left.push(parse(left));
-        list_right.push(parse(right));
+    pub fn similarity_score(&self, left: usize, right: usize) -> Option<u64> {
+        let (left, right) = self.pair(left, right)?;
+        Some(score_similarity(
+            left,
+            right,
+            SimilarityStrategy::choose(left, right),
+        ))
     }
 
-    (list_left, list_right)
+    /// Distance between every two columns, symmetric with a zero diagonal.
+    pub fn distance_matrix(&self) -> Vec<Vec<u64>> {
+        let sorted = self
+            .columns
+            .iter()
+            .map(|column| sorted(column).into_owned())
+            .collect::<Vec<_>>();
+        sorted
+            .iter()
+            .map(|left| {
+                sorted
+                    .iter()
+                    .map(|right| sorted_distance(left, right))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Sum of the distances of all pairs of different columns.
+    pub fn total_distance(&self) -> u64 {
+        self.distance_matrix()
+            .iter()
+            .enumerate()
+            .flat_map(|(row, distances)| &distances[row + 1..])
+            .sum()
+    }
 }
 
-#[aoc(day1, part1, Parsed)]
-pub fn get_total_distance_parsed(input: &(Vec<u32>, Vec<u32>)) -> u32 {
-    let mut list_left = input.0.clone();
-    let mut list_right = input.1.clone();
+/// Reads one row of IDs per line. The number of columns is taken from the
+/// first line unless given, every other line has to match it.
+pub fn try_read_columns(
+    input: &str,
+    separator: Separator,
+    mut width: Option<usize>,
+) -> Result<LocationColumns, ParseError> {
+    let input = normalise(input);
+    let mut columns: Vec<Vec<u32>> = Vec::new();
 
-    list_left.sort_unstable();
-    list_right.sort_unstable();
+    for (line_index, line) in input.lines().enumerate() {
+        let ids = match separator {
+            Separator::Whitespace => line.split_whitespace().collect::<Vec<_>>(),
+            Separator::Char(separator) => line.split(separator).map(str::trim).collect(),
+        };
+        let width = *width.get_or_insert(ids.len().max(1));
+        if ids.len() != width {
+            return Err(ParseError::in_line(
+                1,
+                line_index,
+                line,
+                line,
+                format!("expected {width} location IDs, found {}", ids.len()),
+            ));
+        }
+        columns.resize_with(width, Vec::new);
+        for (column, id) in columns.iter_mut().zip(ids) {
+            column.push(parse_number::<u32>(1, line_index, line, id)?);
+        }
+    }
 
-    list_left
-        .into_iter()
-        .zip(list_right)
-        .map(|(a, b)| a.abs_diff(b))
+    Ok(LocationColumns { columns })
+}
+
+#[aoc(day1, part1, Parsed)]
+pub fn get_total_distance_parsed(input: &(Vec<u32>, Vec<u32>)) -> u64 {
+    sorted_pairs(input).map(|(a, b)| a.abs_diff(b) as u64).sum()
+}
+
+/// Total distance of two lists which are sorted already.
+fn sorted_distance(left: &[u32], right: &[u32]) -> u64 {
+    left.iter()
+        .zip(right)
+        .map(|(a, b)| a.abs_diff(*b) as u64)
         .sum()
 }
 
+/// Pairs the smallest left ID with the smallest right one, and so on.
+fn sorted_pairs(input: &(Vec<u32>, Vec<u32>)) -> impl Iterator<Item = (u32, u32)> {
+    let list_left = sorted(&input.0).into_owned();
+    let list_right = sorted(&input.1).into_owned();
+    list_left.into_iter().zip(list_right)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PairDistance {
+    pub left: u32,
+    pub right: u32,
+    pub distance: u64,
+}
+
+/// The pairing behind the total distance, for auditing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistanceReport {
+    /// in pairing order, smallest IDs first
+    pub pairs: Vec<PairDistance>,
+    pub total: u64,
+    /// distinct IDs of the left list which never appear in the right one
+    pub only_left: Vec<u32>,
+    pub only_right: Vec<u32>,
+}
+
+impl DistanceReport {
+    pub fn new(input: &(Vec<u32>, Vec<u32>)) -> Self {
+        let pairs = sorted_pairs(input)
+            .map(|(left, right)| PairDistance {
+                left,
+                right,
+                distance: left.abs_diff(right) as u64,
+            })
+            .collect::<Vec<_>>();
+        let distinct = |list: &[u32]| list.iter().copied().collect::<BTreeSet<_>>();
+        let (left, right) = (distinct(&input.0), distinct(&input.1));
+        DistanceReport {
+            total: pairs.iter().map(|pair| pair.distance).sum(),
+            pairs,
+            only_left: left.difference(&right).copied().collect(),
+            only_right: right.difference(&left).copied().collect(),
+        }
+    }
+
+    /// The `n` pairs furthest apart, ties in pairing order.
+    pub fn largest(&self, n: usize) -> Vec<PairDistance> {
+        let mut pairs = self.pairs.clone();
+        pairs.sort_by_key(|pair| Reverse(pair.distance));
+        pairs.truncate(n);
+        pairs
+    }
+
+    /// Number of pairs per `bucket_width` wide range of distances, starting
+    /// at the lower bound of each range. Empty ranges are left out.
+    pub fn histogram(&self, bucket_width: u64) -> Vec<(u64, usize)> {
+        let bucket_width = bucket_width.max(1);
+        let mut buckets = BTreeMap::new();
+        for pair in &self.pairs {
+            *buckets
+                .entry(pair.distance / bucket_width * bucket_width)
+                .or_default() += 1;
+        }
+        buckets.into_iter().collect()
+    }
+
+    pub fn to_text(&self, top: usize, bucket_width: u64) -> String {
+        let ids = |ids: &[u32]| ids.iter().map(u32::to_string).collect::<Vec<_>>().join(" ");
+        let mut lines = vec![format!(
+            "{} pairs, total distance {}",
+            self.pairs.len(),
+            self.total
+        )];
+        lines.push(format!("largest {top}:"));
+        for pair in self.largest(top) {
+            lines.push(format!(
+                "  {:>10} {:>10} {:>10}",
+                pair.left, pair.right, pair.distance
+            ));
+        }
+        lines.push("histogram:".to_string());
+        let histogram = self.histogram(bucket_width);
+        let widest = histogram.iter().map(|(_, count)| *count).max().unwrap_or(0);
+        for (start, count) in histogram {
+            let bar = "#".repeat((count * 40).div_ceil(widest));
+            lines.push(format!(
+                "  {:>10}..{:<10} {count:>6} {bar}",
+                start,
+                start + bucket_width.max(1)
+            ));
+        }
+        lines.push(format!("only left: {}", ids(&self.only_left)));
+        lines.push(format!("only right: {}", ids(&self.only_right)));
+        lines.join("\n")
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("left,right,distance\n");
+        for pair in &self.pairs {
+            csv.push_str(&format!("{},{},{}\n", pair.left, pair.right, pair.distance));
+        }
+        csv
+    }
+}
+
+/// How `similarity_score` counts the occurrences in the right list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimilarityStrategy {
+    /// a counting table spanning the smallest to the largest right ID
+    Dense,
+    /// walks both lists in order, needs no extra memory if they are sorted
+    SortedMerge,
+    Hash,
+}
+
+impl SimilarityStrategy {
+    /// Dense as long as the table is not much larger than the lists, then
+    /// merging if both lists happen to be sorted already, hashing otherwise.
+    pub fn choose(list_left: &[u32], list_right: &[u32]) -> Self {
+        let span = match (list_right.iter().min(), list_right.iter().max()) {
+            (Some(min), Some(max)) => (max - min) as usize + 1,
+            _ => 0,
+        };
+        if span <= 4 * (list_left.len() + list_right.len()) + 1024 {
+            SimilarityStrategy::Dense
+        } else if list_left.is_sorted() && list_right.is_sorted() {
+            SimilarityStrategy::SortedMerge
+        } else {
+            SimilarityStrategy::Hash
+        }
+    }
+}
+
+fn sorted(list: &[u32]) -> Cow<'_, [u32]> {
+    if list.is_sorted() {
+        Cow::Borrowed(list)
+    } else {
+        let mut list = list.to_vec();
+        list.sort_unstable();
+        Cow::Owned(list)
+    }
+}
+
+pub fn similarity_score(
+    (list_left, list_right): &(Vec<u32>, Vec<u32>),
+    strategy: SimilarityStrategy,
+) -> u64 {
+    score_similarity(list_left, list_right, strategy)
+}
+
+fn score_similarity(list_left: &[u32], list_right: &[u32], strategy: SimilarityStrategy) -> u64 {
+    let score = |id: u32, count: u32| id as u64 * count as u64;
+    match strategy {
+        SimilarityStrategy::Dense => {
+            let Some(min) = list_right.iter().min().copied() else {
+                return 0;
+            };
+            let max = list_right.iter().max().copied().unwrap_or(min);
+            let mut counts = vec![0u32; (max - min) as usize + 1];
+            for id in list_right {
+                counts[(id - min) as usize] += 1;
+            }
+            list_left
+                .iter()
+                .filter(|id| (min..=max).contains(*id))
+                .map(|id| score(*id, counts[(id - min) as usize]))
+                .sum()
+        }
+        SimilarityStrategy::SortedMerge => {
+            let (list_left, list_right) = (sorted(list_left), sorted(list_right));
+            let mut right = list_right.iter().copied().peekable();
+            let mut previous = None;
+            list_left
+                .iter()
+                .map(|&id| {
+                    let count = match previous {
+                        Some((previous_id, count)) if previous_id == id => count,
+                        _ => {
+                            while right.next_if(|right_id| *right_id < id).is_some() {}
+                            let mut count = 0;
+                            while right.next_if(|right_id| *right_id == id).is_some() {
+                                count += 1;
+                            }
+                            count
+                        }
+                    };
+                    previous = Some((id, count));
+                    score(id, count)
+                })
+                .sum()
+        }
+        SimilarityStrategy::Hash => {
+            let mut counts = HashMap::<u32, u32>::with_capacity(list_right.len());
+            for id in list_right {
+                *counts.entry(*id).or_default() += 1;
+            }
+            list_left
+                .iter()
+                .map(|id| score(*id, counts.get(id).copied().unwrap_or(0)))
+                .sum()
+        }
+    }
+}
+
 #[aoc(day1, part2)]
-pub fn get_similarity_score(input: &(Vec<u32>, Vec<u32>)) -> u32 {
-    let list_left = &input.0;
-    let list_right = &input.1;
+pub fn get_similarity_score(input: &(Vec<u32>, Vec<u32>)) -> u64 {
+    similarity_score(input, SimilarityStrategy::choose(&input.0, &input.1))
+}
 
-    let max_val = list_right.iter().max().unwrap_or(&0);
+#[aoc(day1, part2, Dense)]
+fn get_similarity_score_dense(input: &(Vec<u32>, Vec<u32>)) -> u64 {
+    similarity_score(input, SimilarityStrategy::Dense)
+}
 
-    let mut map: Vec<u32> = vec![0; (max_val + 1) as usize];
+#[aoc(day1, part2, SortedMerge)]
+fn get_similarity_score_sorted_merge(input: &(Vec<u32>, Vec<u32>)) -> u64 {
+    similarity_score(input, SimilarityStrategy::SortedMerge)
+}
 
-    for item in list_right {
-        map[*item as usize] += 1;
+#[aoc(day1, part2, Hash)]
+fn get_similarity_score_hash(input: &(Vec<u32>, Vec<u32>)) -> u64 {
+    similarity_score(input, SimilarityStrategy::Hash)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Node of `DistanceTree`, covering a range of IDs.
+#[derive(Debug, Clone, Copy, Default)]
+struct DistanceNode {
+    min: i64,
+    max: i64,
+    /// sum of |D| over the covered IDs
+    abs_sum: u64,
+    /// pending addition for the children
+    lazy: i64,
+    /// index of the left child, the right one follows it, 0 if not split yet
+    children: usize,
+}
+
+/// D(x) = (left IDs <= x) - (right IDs <= x) for every x in the u32 range.
+/// For two lists of equal length, sum |D(x)| is the total distance of their
+/// sorted pairs, as every pair (a, b) adds 1 to |D| for each x in a..b.
+/// Nodes are only split where D is not uniform, and merged back once it is
+/// again, their slots being reused for later splits.
+#[derive(Debug, Clone)]
+struct DistanceTree {
+    nodes: Vec<DistanceNode>,
+    /// index of the left child of merged pairs, free to reuse
+    free: Vec<usize>,
+}
+
+impl DistanceTree {
+    const MAX: u64 = u32::MAX as u64;
+
+    fn new() -> Self {
+        DistanceTree {
+            nodes: vec![DistanceNode::default()],
+            free: Vec::new(),
+        }
+    }
+
+    fn total(&self) -> u64 {
+        self.nodes[0].abs_sum
+    }
+
+    /// Adds `delta` to D(x) for all x >= `from`, returns the number of nodes
+    /// visited.
+    fn add_suffix(&mut self, from: u32, delta: i64) -> usize {
+        self.add(0, 0, Self::MAX, from as u64, delta)
     }
 
-    list_left.iter().fold(0, |acc, item| {
-        acc + if item <= max_val {
-            map[*item as usize] * item
+    fn apply(&mut self, index: usize, width: u64, delta: i64) {
+        let node = &mut self.nodes[index];
+        node.abs_sum = if node.min == node.max {
+            (node.min + delta).unsigned_abs() * width
+        } else if node.min >= 0 && node.min + delta >= 0 {
+            node.abs_sum.wrapping_add_signed(delta * width as i64)
+        } else {
+            // all at or below 0, checked by `add`
+            node.abs_sum.wrapping_add_signed(-delta * width as i64)
+        };
+        node.min += delta;
+        node.max += delta;
+        node.lazy += delta;
+    }
+
+    fn add(&mut self, index: usize, low: u64, high: u64, from: u64, delta: i64) -> usize {
+        if high < from {
+            return 1;
+        }
+        let node = self.nodes[index];
+        let keeps_sign = node.min == node.max
+            || (node.min >= 0 && node.min + delta >= 0)
+            || (node.max <= 0 && node.max + delta <= 0);
+        if from <= low && keeps_sign {
+            self.apply(index, high - low + 1, delta);
+            return 1;
+        }
+
+        let middle = low + (high - low) / 2;
+        let children = if node.children == 0 {
+            // uniform so far, both halves start with the same D
+            let child = DistanceNode {
+                min: node.min,
+                max: node.max,
+                abs_sum: node.min.unsigned_abs(),
+                lazy: 0,
+                children: 0,
+            };
+            let pair = [
+                DistanceNode {
+                    abs_sum: child.abs_sum * (middle - low + 1),
+                    ..child
+                },
+                DistanceNode {
+                    abs_sum: child.abs_sum * (high - middle),
+                    ..child
+                },
+            ];
+            let children = match self.free.pop() {
+                Some(children) => {
+                    self.nodes[children..children + 2].copy_from_slice(&pair);
+                    children
+                }
+                None => {
+                    self.nodes.extend(pair);
+                    self.nodes.len() - 2
+                }
+            };
+            // the pending additions are part of the children already
+            self.nodes[index].children = children;
+            self.nodes[index].lazy = 0;
+            children
         } else {
-            0
+            if node.lazy != 0 {
+                self.apply(node.children, middle - low + 1, node.lazy);
+                self.apply(node.children + 1, high - middle, node.lazy);
+                self.nodes[index].lazy = 0;
+            }
+            node.children
+        };
+        let visited = self.add(children, low, middle, from, delta)
+            + self.add(children + 1, middle + 1, high, from, delta);
+
+        let (left, right) = (self.nodes[children], self.nodes[children + 1]);
+        let node = &mut self.nodes[index];
+        node.min = left.min.min(right.min);
+        node.max = left.max.max(right.max);
+        node.abs_sum = left.abs_sum + right.abs_sum;
+        if node.min == node.max {
+            // uniform children are never split, so nothing hangs below them
+            node.children = 0;
+            self.free.push(children);
         }
-    })
+        visited + 1
+    }
 }
 
-pub use get_similarity_score_direct as part2;
-pub use get_total_distance_direct as part1;
+/// Multiset of one list, split into the IDs paired up with the other list
+/// (the smallest ones) and the surplus a longer list has.
+#[derive(Debug, Clone, Default)]
+struct SideList {
+    paired: BTreeMap<u32, u32>,
+    surplus: BTreeMap<u32, u32>,
+    paired_len: usize,
+    surplus_len: usize,
+}
+
+fn multiset_insert(set: &mut BTreeMap<u32, u32>, id: u32) {
+    *set.entry(id).or_default() += 1;
+}
+
+fn multiset_remove(set: &mut BTreeMap<u32, u32>, id: u32) -> bool {
+    match set.get_mut(&id) {
+        Some(1) => set.remove(&id).is_some(),
+        Some(count) => {
+            *count -= 1;
+            true
+        }
+        None => false,
+    }
+}
+
+impl SideList {
+    fn len(&self) -> usize {
+        self.paired_len + self.surplus_len
+    }
+
+    fn count(&self, id: u32) -> u32 {
+        self.paired.get(&id).unwrap_or(&0) + self.surplus.get(&id).unwrap_or(&0)
+    }
+}
+
+/// Both location lists, kept up to date with the total distance and the
+/// similarity score as IDs come and go.
+///
+/// Similarity updates are O(log n). Distance updates change D on a suffix of
+/// IDs, which costs O(log U) (U being the 2^32 possible IDs) plus O(log U)
+/// for every stretch of that suffix where D changes sign. That is not
+/// logarithmic in the worst case: with interleaved lists D keeps switching
+/// between 0 and 1, and taking 1 off it costs O(n log U). Memory follows the
+/// IDs in the lists, not the updates made so far.
+///
+/// Like the batch functions, lists of different length only pair up as many
+/// IDs as the shorter one has.
+#[derive(Debug, Clone)]
+pub struct LocationLists {
+    left: SideList,
+    right: SideList,
+    distance: DistanceTree,
+    similarity: u64,
+}
+
+impl Default for LocationLists {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocationLists {
+    pub fn new() -> Self {
+        LocationLists {
+            left: SideList::default(),
+            right: SideList::default(),
+            distance: DistanceTree::new(),
+            similarity: 0,
+        }
+    }
+
+    pub fn from_lists((list_left, list_right): &(Vec<u32>, Vec<u32>)) -> Self {
+        let mut lists = Self::new();
+        for id in list_left {
+            lists.insert(Side::Left, *id);
+        }
+        for id in list_right {
+            lists.insert(Side::Right, *id);
+        }
+        lists
+    }
+
+    pub fn len(&self, side: Side) -> usize {
+        self.side(side).0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left.len() + self.right.len() == 0
+    }
+
+    pub fn total_distance(&self) -> u64 {
+        self.distance.total()
+    }
+
+    pub fn similarity_score(&self) -> u64 {
+        self.similarity
+    }
+
+    fn side(&self, side: Side) -> (&SideList, &SideList) {
+        match side {
+            Side::Left => (&self.left, &self.right),
+            Side::Right => (&self.right, &self.left),
+        }
+    }
+
+    fn side_mut(&mut self, side: Side) -> &mut SideList {
+        match side {
+            Side::Left => &mut self.left,
+            Side::Right => &mut self.right,
+        }
+    }
+
+    fn pair(&mut self, side: Side, id: u32) {
+        let list = self.side_mut(side);
+        multiset_insert(&mut list.paired, id);
+        list.paired_len += 1;
+        let delta = if side == Side::Left { 1 } else { -1 };
+        self.distance.add_suffix(id, delta);
+    }
+
+    fn unpair(&mut self, side: Side, id: u32) {
+        let list = self.side_mut(side);
+        multiset_remove(&mut list.paired, id);
+        list.paired_len -= 1;
+        let delta = if side == Side::Left { -1 } else { 1 };
+        self.distance.add_suffix(id, delta);
+    }
+
+    fn add_surplus(&mut self, side: Side, id: u32) {
+        let list = self.side_mut(side);
+        multiset_insert(&mut list.surplus, id);
+        list.surplus_len += 1;
+    }
+
+    fn take_smallest_surplus(&mut self, side: Side) -> Option<u32> {
+        let list = self.side_mut(side);
+        let id = *list.surplus.keys().next()?;
+        multiset_remove(&mut list.surplus, id);
+        list.surplus_len -= 1;
+        Some(id)
+    }
+
+    pub fn insert(&mut self, side: Side, id: u32) {
+        let other = match side {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        };
+        let (list, other_list) = self.side(side);
+        let (shorter, matches) = (list.len() < other_list.len(), other_list.count(id));
+        self.similarity += id as u64 * matches as u64;
+
+        if shorter {
+            // one more pair, taking the smallest unpaired ID of the other side
+            self.pair(side, id);
+            if let Some(other_id) = self.take_smallest_surplus(other) {
+                self.pair(other, other_id);
+            }
+        } else {
+            match self.side(side).0.paired.keys().next_back().copied() {
+                Some(largest) if id < largest => {
+                    self.unpair(side, largest);
+                    self.add_surplus(side, largest);
+                    self.pair(side, id);
+                }
+                _ => self.add_surplus(side, id),
+            }
+        }
+    }
+
+    /// Removes one occurrence of `id`, false if there is none.
+    pub fn remove(&mut self, side: Side, id: u32) -> bool {
+        let other = match side {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        };
+        let (list, other_list) = self.side(side);
+        if list.count(id) == 0 {
+            return false;
+        }
+        let (longer, matches) = (list.len() > other_list.len(), other_list.count(id));
+        self.similarity -= id as u64 * matches as u64;
+
+        let list = self.side_mut(side);
+        if multiset_remove(&mut list.surplus, id) {
+            list.surplus_len -= 1;
+            return true;
+        }
+        self.unpair(side, id);
+        if longer {
+            let replacement = self
+                .take_smallest_surplus(side)
+                .expect("a longer list has surplus IDs");
+            self.pair(side, replacement);
+        } else {
+            // one pair less, the other side's largest paired ID is now surplus
+            let (other_list, _) = self.side(other);
+            if let Some(largest) = other_list.paired.keys().next_back().copied() {
+                self.unpair(other, largest);
+                self.add_surplus(other, largest);
+            }
+        }
+        true
+    }
+}
+
+#[aoc(day1, part1, Incremental)]
+fn get_total_distance_incremental(input: &(Vec<u32>, Vec<u32>)) -> u64 {
+    LocationLists::from_lists(input).total_distance()
+}
+
+#[aoc(day1, part2, Incremental)]
+fn get_similarity_score_incremental(input: &(Vec<u32>, Vec<u32>)) -> u64 {
+    LocationLists::from_lists(input).similarity_score()
+}
+
+pub fn part1(input: &str) -> Result<Answer, SolveError> {
+    get_total_distance_direct(input).into_answer()
+}
+
+pub fn part2(input: &str) -> Result<Answer, SolveError> {
+    get_similarity_score_direct(input).into_answer()
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: u8 = 1;
+    const VARIANTS: &'static [Variant] = &[
+        Variant::new(1, "Direct", raw_input, |raw| {
+            get_total_distance_direct(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(1, "Parsed", parse_input::<Self>, |parsed| {
+            get_total_distance_parsed(input(parsed)).into_answer()
+        }),
+        Variant::new(1, "Incremental", parse_input::<Self>, |parsed| {
+            get_total_distance_incremental(input(parsed)).into_answer()
+        }),
+        Variant::new(2, "Direct", raw_input, |raw| {
+            get_similarity_score_direct(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(2, DEFAULT_VARIANT, parse_input::<Self>, |parsed| {
+            get_similarity_score(input(parsed)).into_answer()
+        }),
+        Variant::new(2, "Dense", parse_input::<Self>, |parsed| {
+            get_similarity_score_dense(input(parsed)).into_answer()
+        }),
+        Variant::new(2, "SortedMerge", parse_input::<Self>, |parsed| {
+            get_similarity_score_sorted_merge(input(parsed)).into_answer()
+        }),
+        Variant::new(2, "Hash", parse_input::<Self>, |parsed| {
+            get_similarity_score_hash(input(parsed)).into_answer()
+        }),
+        Variant::new(2, "Incremental", parse_input::<Self>, |parsed| {
+            get_similarity_score_incremental(input(parsed)).into_answer()
+        }),
+    ];
+    type Input = (Vec<u32>, Vec<u32>);
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        try_input_generator(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(get_total_distance_parsed(input))
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(get_similarity_score(input))
+    }
+}
 
 #[cfg(test)]
 mod test {
-    use super::{get_similarity_score, get_total_distance_parsed};
+    use super::{
+        get_similarity_score, get_total_distance_parsed, similarity_score, try_input_generator,
+        try_read_columns, DistanceReport, LocationColumns, LocationLists, PairDistance, Separator,
+        Side, SimilarityStrategy,
+    };
+    use crate::random::Rng;
 
     #[test]
     fn sample1() {
@@ -85,4 +802,237 @@ mod test {
         assert_eq!(get_total_distance_parsed(&sample), 11);
         assert_eq!(get_similarity_score(&sample), 31);
     }
+
+    #[test]
+    fn test_similarity_strategies() {
+        let strategies = [
+            SimilarityStrategy::Dense,
+            SimilarityStrategy::SortedMerge,
+            SimilarityStrategy::Hash,
+        ];
+        let sample = (vec![3, 4, 2, 1, 3, 3], vec![4, 3, 5, 3, 9, 3]);
+        for strategy in strategies {
+            assert_eq!(similarity_score(&sample, strategy), 31, "{strategy:?}");
+            assert_eq!(similarity_score(&(vec![1], vec![]), strategy), 0);
+        }
+
+        // would not fit into a counting table, nor the sum into a u32
+        let huge = (vec![u32::MAX, 7, u32::MAX], vec![u32::MAX, 0, u32::MAX]);
+        assert_eq!(
+            SimilarityStrategy::choose(&huge.0, &huge.1),
+            SimilarityStrategy::Hash
+        );
+        for strategy in &strategies[1..] {
+            assert_eq!(similarity_score(&huge, *strategy), 4 * u32::MAX as u64);
+        }
+        assert_eq!(
+            get_total_distance_parsed(&(vec![u32::MAX; 2], vec![0; 2])),
+            2 * u32::MAX as u64
+        );
+
+        let sorted = (vec![0, 7, u32::MAX], vec![0, u32::MAX]);
+        assert_eq!(
+            SimilarityStrategy::choose(&sorted.0, &sorted.1),
+            SimilarityStrategy::SortedMerge
+        );
+        assert_eq!(
+            SimilarityStrategy::choose(&sample.0, &sample.1),
+            SimilarityStrategy::Dense
+        );
+    }
+
+    #[test]
+    fn test_try_input_generator() {
+        assert_eq!(
+            try_input_generator("3   4\n4   3"),
+            Ok((vec![3, 4], vec![4, 3]))
+        );
+        assert_eq!(
+            try_input_generator("\u{feff}3\t4 \r\n4   3\r\n"),
+            Ok((vec![3, 4], vec![4, 3]))
+        );
+        assert!(try_input_generator("3   4   5").is_err());
+        let error = try_input_generator("3   4\n4   x3\n").unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.text.as_str()),
+            (2, 5, "x3")
+        );
+        let error = try_input_generator("3   4\n\n4   3").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+    }
+
+    #[test]
+    fn test_distance_report() {
+        let sample = (vec![3, 4, 2, 1, 3, 3], vec![4, 3, 5, 3, 9, 3]);
+        let report = DistanceReport::new(&sample);
+        assert_eq!(report.total, 11);
+        assert_eq!(
+            report.pairs[0],
+            PairDistance {
+                left: 1,
+                right: 3,
+                distance: 2
+            }
+        );
+        assert_eq!(
+            report
+                .largest(2)
+                .iter()
+                .map(|pair| (pair.left, pair.right))
+                .collect::<Vec<_>>(),
+            vec![(4, 9), (1, 3)]
+        );
+        assert_eq!(report.histogram(2), vec![(0, 3), (2, 2), (4, 1)]);
+        assert_eq!(
+            (report.only_left, report.only_right),
+            (vec![1, 2], vec![5, 9])
+        );
+
+        let report = DistanceReport::new(&(vec![2, 1], vec![7, 3]));
+        assert_eq!(report.to_csv(), "left,right,distance\n1,3,2\n2,7,5\n");
+        let text = report.to_text(1, 10);
+        assert!(
+            text.starts_with("2 pairs, total distance 7\nlargest 1:\n"),
+            "{text}"
+        );
+        assert!(text.contains("only right: 3 7"), "{text}");
+    }
+
+    #[test]
+    fn test_read_columns() {
+        let columns =
+            try_read_columns("3, 4, 1\n4,3,1\n2 ,5,9", Separator::Char(','), None).unwrap();
+        assert_eq!(
+            columns,
+            LocationColumns {
+                columns: vec![vec![3, 4, 2], vec![4, 3, 5], vec![1, 1, 9]]
+            }
+        );
+        let tabs = try_read_columns("3\t4\t1\n4\t3\t1\n2\t5\t9", Separator::Char('\t'), None);
+        assert_eq!(tabs, Ok(columns.clone()));
+        assert_eq!(columns.width(), 3);
+
+        let error = try_read_columns("1 2 3\n1 2", Separator::Whitespace, None).unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.reason, "expected 3 location IDs, found 2");
+        let error = try_read_columns("1,,3", Separator::Char(','), None).unwrap_err();
+        assert_eq!((error.line, error.column), (1, 3));
+    }
+
+    #[test]
+    fn test_columns() {
+        let sample = (vec![3, 4, 2, 1, 3, 3], vec![4, 3, 5, 3, 9, 3]);
+        let columns = LocationColumns {
+            columns: vec![sample.0.clone(), sample.1.clone(), sample.0.clone()],
+        };
+        assert_eq!(columns.distance(0, 1), Some(11));
+        assert_eq!(columns.similarity_score(0, 1), Some(31));
+        assert_eq!(
+            columns.pair(1, 0),
+            Some((sample.1.as_slice(), sample.0.as_slice()))
+        );
+        assert_eq!(columns.distance(0, 3), None);
+        assert_eq!(columns.similarity_score(3, 0), None);
+        assert_eq!(columns.pair(5, 5), None);
+        assert_eq!(
+            columns.distance_matrix(),
+            vec![vec![0, 11, 0], vec![11, 0, 11], vec![0, 11, 0]]
+        );
+        assert_eq!(columns.total_distance(), 22);
+    }
+
+    #[test]
+    fn test_location_lists() {
+        let sample = (vec![3, 4, 2, 1, 3, 3], vec![4, 3, 5, 3, 9, 3]);
+        let mut lists = LocationLists::from_lists(&sample);
+        assert_eq!((lists.total_distance(), lists.similarity_score()), (11, 31));
+        assert!(lists.remove(Side::Right, 9));
+        assert!(!lists.remove(Side::Right, 9));
+        // 3 3 3 4 5 paired with 1 2 3 3 3, the 4 on the left has no partner
+        assert_eq!(lists.total_distance(), 6);
+        assert_eq!(lists.len(Side::Left), 6);
+
+        let mut lists = LocationLists::new();
+        lists.insert(Side::Left, u32::MAX);
+        lists.insert(Side::Right, 0);
+        assert_eq!(lists.total_distance(), u32::MAX as u64);
+    }
+
+    #[test]
+    fn test_location_lists_reuse_nodes() {
+        let mut rng = Rng::new(5);
+        let mut lists = LocationLists::new();
+        let ids = (0..200)
+            .map(|_| (rng.range(0..=1000) as u32, rng.range(0..=1000) as u32))
+            .collect::<Vec<_>>();
+        let mut peak = 0;
+        for _ in 0..3 {
+            for (left, right) in &ids {
+                lists.insert(Side::Left, *left);
+                lists.insert(Side::Right, *right);
+            }
+            peak = peak.max(lists.distance.nodes.len());
+            for (left, right) in &ids {
+                assert!(lists.remove(Side::Left, *left));
+                assert!(lists.remove(Side::Right, *right));
+            }
+            assert_eq!(lists.total_distance(), 0);
+            // only the root is left in use
+            let distance = &lists.distance;
+            assert_eq!(distance.nodes.len() - 2 * distance.free.len(), 1);
+        }
+        assert_eq!(lists.distance.nodes.len(), peak);
+    }
+
+    #[test]
+    fn test_location_lists_update_cost() {
+        let interleaved = |n: u32| {
+            let lists = (
+                (0..n).map(|i| 2 * i).collect(),
+                (0..n).map(|i| 2 * i + 1).collect(),
+            );
+            LocationLists::from_lists(&lists).distance
+        };
+        // D is 1 on the left IDs and 0 on the right ones: adding to it keeps
+        // its sign, taking 1 off (a small right ID) flips every other stretch
+        let visits = [256, 1024].map(|n| {
+            let distance = interleaved(n);
+            assert!(distance.clone().add_suffix(0, 1) <= 2 * 33);
+            distance.clone().add_suffix(0, -1)
+        });
+        assert!(visits[0] >= 2 * 256, "{visits:?}");
+        assert!(visits[1] >= 3 * visits[0], "{visits:?}");
+        assert!(visits[1] <= 4 * 33 * 2 * 1024, "{visits:?}");
+    }
+
+    #[test]
+    fn test_location_lists_agree_with_batch() {
+        let mut rng = Rng::new(12);
+        let mut lists = LocationLists::new();
+        let mut batch = (Vec::new(), Vec::new());
+        for _ in 0..2000 {
+            let side = if rng.chance(0.5) {
+                Side::Left
+            } else {
+                Side::Right
+            };
+            let list = match side {
+                Side::Left => &mut batch.0,
+                Side::Right => &mut batch.1,
+            };
+            // small IDs, to get duplicates and ties
+            let id = rng.range(0..=40) as u32;
+            if rng.chance(0.35) {
+                let removed = list.iter().position(|other| *other == id).map(|index| {
+                    list.swap_remove(index);
+                });
+                assert_eq!(lists.remove(side, id), removed.is_some());
+            } else {
+                list.push(id);
+                lists.insert(side, id);
+            }
+            assert_eq!(lists.total_distance(), get_total_distance_parsed(&batch));
+            assert_eq!(lists.similarity_score(), get_similarity_score(&batch));
+        }
+    }
 }
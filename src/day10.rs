@@ -1,112 +1,90 @@
-use ndarray::Array2;
+use crate::error::{ParseError, SolveError};
+use crate::grid::{Grid, Position};
+use crate::solution::{input, raw_input, Answer, IntoAnswer, Solution, Variant, DEFAULT_VARIANT};
 use std::collections::HashSet;
-use std::sync::{Arc, RwLock};
-type Position = (usize, usize);
 
-const DIRECTIONS: &[(isize, isize)] = &[
-    // (-1, -1),
-    (-1, 0),
-    // (-1, 1),
-    (0, -1),
-    (0, 1),
-    // (1, -1),
-    (1, 0),
-    // (1, 1),
-];
-
-fn get_trailhead_score(starting_pos: &Position, map: &Array2<u8>) -> Vec<Position> {
+fn get_trailhead_score(starting_pos: &Position, map: &Grid<u8>) -> Vec<Position> {
     let starting_value = map[*starting_pos];
     if starting_value == 9 {
         vec![*starting_pos]
     } else {
-        let bounds = 0..map.dim().0 as isize;
-
-        DIRECTIONS
-            .iter()
-            .filter_map(|direction| {
-                let target_pos = (
-                    starting_pos.0 as isize + direction.0,
-                    starting_pos.1 as isize + direction.1,
-                );
-                if bounds.contains(&target_pos.0) && bounds.contains(&target_pos.1) {
-                    Some((target_pos.0 as usize, target_pos.1 as usize))
-                } else {
-                    None
-                }
-            })
+        map.neighbours4(*starting_pos)
             .filter(|target_pos| map[*target_pos] == starting_value + 1)
-            .map(|new_starting_pos| {
-                get_trailhead_score(&new_starting_pos, map)
-                    .into_iter()
-                    .collect::<Vec<_>>()
-            })
-            .flatten()
+            .flat_map(|new_starting_pos| get_trailhead_score(&new_starting_pos, map))
             .collect()
     }
 }
 
 #[aoc(day10, part1)]
-pub fn sum_trailheads_score_unique(input: &str) -> u32 {
-    sum_trailheads_score(input, true)
+pub fn sum_trailheads_score_unique(input: &str) -> Result<u32, ParseError> {
+    Ok(sum_trailheads_score(&try_generate_topography(input)?, true))
+}
+pub fn part1(input: &str) -> Result<Answer, SolveError> {
+    sum_trailheads_score_unique(input).into_answer()
 }
-pub use sum_trailheads_score_unique as part1;
 
 #[aoc(day10, part2)]
-pub fn sum_trailheads_score_non_unique(input: &str) -> u32 {
-    sum_trailheads_score(input, false)
+pub fn sum_trailheads_score_non_unique(input: &str) -> Result<u32, ParseError> {
+    Ok(sum_trailheads_score(
+        &try_generate_topography(input)?,
+        false,
+    ))
+}
+pub fn part2(input: &str) -> Result<Answer, SolveError> {
+    sum_trailheads_score_non_unique(input).into_answer()
 }
-pub use sum_trailheads_score_non_unique as part2;
-
-fn sum_trailheads_score(input: &str, unique_finish: bool) -> u32 {
-    let trailhead_pos = Arc::new(RwLock::new(HashSet::<Position>::with_capacity(1000)));
-    let dimension = input.find('\n').unwrap();
-
-    let collected = input
-        .lines()
-        .enumerate()
-        .flat_map(|(line_no, line)| {
-            let trailhead_pos = &trailhead_pos;
-            line.chars().enumerate().map(move |(ch_no, ch)| {
-                let digit = ch as u8 - '0' as u8;
-                if digit == 0 {
-                    trailhead_pos
-                        .write()
-                        .map(|mut trailhead_pos| trailhead_pos.insert((line_no, ch_no)))
-                        .unwrap();
-                }
-                digit
-            })
-        })
-        .collect::<Vec<u8>>();
 
-    let map = Array2::from_shape_vec((dimension, dimension), collected).unwrap();
+pub fn try_generate_topography(input: &str) -> Result<Grid<u8>, ParseError> {
+    Grid::try_parse(10, input, |ch| ch.to_digit(10).map(|height| height as u8))
+}
 
-    let sum = trailhead_pos
-        .read()
-        .unwrap()
-        .iter()
+fn sum_trailheads_score(map: &Grid<u8>, unique_finish: bool) -> u32 {
+    map.positions(|height| *height == 0)
         .map(|trailhead| {
             if unique_finish {
-                get_trailhead_score(trailhead, &map)
+                get_trailhead_score(&trailhead, map)
                     .into_iter()
                     .collect::<HashSet<_>>()
-                    .into_iter()
-                    .collect::<Vec<_>>()
+                    .len()
             } else {
-                get_trailhead_score(trailhead, &map)
-                    .into_iter()
-                    .collect::<Vec<_>>()
+                get_trailhead_score(&trailhead, map).len()
             }
         })
-        .flatten()
-        .count() as u32;
+        .sum::<usize>() as u32
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    const DAY: u8 = 10;
+    const VARIANTS: &'static [Variant] = &[
+        Variant::new(1, DEFAULT_VARIANT, raw_input, |raw| {
+            sum_trailheads_score_unique(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(2, DEFAULT_VARIANT, raw_input, |raw| {
+            sum_trailheads_score_non_unique(input::<String>(raw)).into_answer()
+        }),
+    ];
+    type Input = Grid<u8>;
 
-    sum
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        try_generate_topography(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(sum_trailheads_score(input, true) as Answer)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(sum_trailheads_score(input, false) as Answer)
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{sum_trailheads_score_non_unique, sum_trailheads_score_unique};
+    use super::{
+        sum_trailheads_score_non_unique, sum_trailheads_score_unique, try_generate_topography,
+    };
 
     const INPUT: &str = r"89010123
 78121874
@@ -117,12 +95,18 @@ mod test {
 01329801
 10456732";
 
+    #[test]
+    fn test_try_generator() {
+        let error = try_generate_topography("0123\n1.34").unwrap_err();
+        assert_eq!((error.day, error.line, error.column), (10, 2, 2));
+    }
+
     #[test]
     fn test_pt1() {
-        assert_eq!(sum_trailheads_score_unique(INPUT), 36);
+        assert_eq!(sum_trailheads_score_unique(INPUT), Ok(36));
     }
     #[test]
     fn test_pt2() {
-        assert_eq!(sum_trailheads_score_non_unique(INPUT), 81);
+        assert_eq!(sum_trailheads_score_non_unique(INPUT), Ok(81));
     }
 }
@@ -1,3 +1,6 @@
+use crate::error::{parse_number, ParseError, SolveError};
+use crate::input::normalise;
+use crate::solution::{input, raw_input, Answer, IntoAnswer, Solution, Variant, DEFAULT_VARIANT};
 use std::{
     collections::HashMap,
     sync::{Arc, RwLock},
@@ -5,6 +8,11 @@ use std::{
 
 type Stone = usize;
 
+#[allow(
+    clippy::map_clone,
+    clippy::clone_on_copy,
+    clippy::manual_is_multiple_of
+)]
 fn blink(stone: &Stone, depth: u8, lookup: Arc<RwLock<HashMap<(Stone, u8), usize>>>) -> usize {
     // println!("reached depth: {}", depth);
     if depth == 0 {
@@ -49,31 +57,88 @@ fn blink(stone: &Stone, depth: u8, lookup: Arc<RwLock<HashMap<(Stone, u8), usize
     }
 }
 
-#[aoc(day11, part1)]
-pub fn count_stones_after_blink(input: &str) -> usize {
+pub fn try_parse_stones(input: &str) -> Result<Vec<Stone>, ParseError> {
+    normalise(input)
+        .lines()
+        .enumerate()
+        .map(|(line_index, line)| {
+            line.split_whitespace()
+                .map(|stone_str| parse_number::<Stone>(11, line_index, line, stone_str))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(|lines| lines.concat())
+}
+
+fn count_stones(stones: &[Stone], depth: u8) -> usize {
     let lookup = Arc::new(RwLock::new(HashMap::new()));
-    input.split(" ").fold(0, |acc, stone_str| {
-        acc + blink(&stone_str.parse::<Stone>().unwrap(), 25, lookup.clone())
-    })
+    stones
+        .iter()
+        .fold(0, |acc, stone| acc + blink(stone, depth, lookup.clone()))
+}
+
+#[aoc(day11, part1)]
+pub fn count_stones_after_blink(input: &str) -> Result<usize, ParseError> {
+    Ok(count_stones(&try_parse_stones(input)?, 25))
 }
 
 #[aoc(day11, part2)]
-pub fn count_stones_after_blink_many(input: &str) -> usize {
-    let lookup = Arc::new(RwLock::new(HashMap::new()));
-    input.split(" ").fold(0, |acc, stone_str| {
-        acc + blink(&stone_str.parse::<Stone>().unwrap(), 75, lookup.clone())
-    })
+pub fn count_stones_after_blink_many(input: &str) -> Result<usize, ParseError> {
+    Ok(count_stones(&try_parse_stones(input)?, 75))
+}
+
+pub fn part1(input: &str) -> Result<Answer, SolveError> {
+    count_stones_after_blink(input).into_answer()
+}
+
+pub fn part2(input: &str) -> Result<Answer, SolveError> {
+    count_stones_after_blink_many(input).into_answer()
 }
 
-pub use count_stones_after_blink as part1;
-pub use count_stones_after_blink_many as part2;
+pub struct Day11;
+
+impl Solution for Day11 {
+    const DAY: u8 = 11;
+    const VARIANTS: &'static [Variant] = &[
+        Variant::new(1, DEFAULT_VARIANT, raw_input, |raw| {
+            count_stones_after_blink(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(2, DEFAULT_VARIANT, raw_input, |raw| {
+            count_stones_after_blink_many(input::<String>(raw)).into_answer()
+        }),
+    ];
+    type Input = Vec<Stone>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        try_parse_stones(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(count_stones(input, 25) as Answer)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(count_stones(input, 75) as Answer)
+    }
+}
 
 #[cfg(test)]
 mod test {
-    use super::count_stones_after_blink;
+    use super::{count_stones_after_blink, try_parse_stones};
+
+    #[test]
+    fn test_try_parse_stones() {
+        assert_eq!(try_parse_stones("125 17"), Ok(vec![125, 17]));
+        assert_eq!(try_parse_stones("125  17\r\n"), Ok(vec![125, 17]));
+        let error = try_parse_stones("125 1x7").unwrap_err();
+        assert_eq!((error.day, error.line, error.column), (11, 1, 5));
+        let error = try_parse_stones("125 17\n0 1x7").unwrap_err();
+        assert_eq!((error.day, error.line, error.column), (11, 2, 3));
+        assert_eq!(error.text, "1x7");
+    }
 
     #[test]
     fn test_part1() {
-        assert_eq!(count_stones_after_blink("125 17"), 55312);
+        assert_eq!(count_stones_after_blink("125 17"), Ok(55312));
     }
 }
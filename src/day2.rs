@@ -1,24 +1,90 @@
-use std::cmp::Ordering;
+use crate::error::{parse_number, ParseError, SolveError};
+use crate::input::normalise;
+use crate::solution::{input, parse_input, raw_input, Answer, IntoAnswer, Solution, Variant};
+use rayon::prelude::*;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::BufRead;
+use std::ops::{AddAssign, RangeInclusive};
+
 #[aoc(day2, part1, DirectIterator)]
-pub fn count_safe_reports_direct(input: &str) -> u32 {
-    count_safe_reports_iterator(&input_generator(input))
+pub fn count_safe_reports_direct(input: &str) -> Result<u32, ParseError> {
+    Ok(count_safe_reports_iterator(&try_input_generator(input)?))
 }
 #[aoc(day2, part2, DirectIterator)]
-pub fn count_safe_reports_damped_direct(input: &str) -> u32 {
-    count_safe_reports_damped_iterator(&input_generator(input))
+pub fn count_safe_reports_damped_direct(input: &str) -> Result<u32, ParseError> {
+    Ok(count_safe_reports_damped_iterator(&try_input_generator(
+        input,
+    )?))
+}
+
+pub fn part1(input: &str) -> Result<Answer, SolveError> {
+    count_safe_reports_direct(input).into_answer()
+}
+
+pub fn part2(input: &str) -> Result<Answer, SolveError> {
+    count_safe_reports_damped_direct(input).into_answer()
 }
 
-pub use count_safe_reports_damped_direct as part2;
-pub use count_safe_reports_direct as part1;
+pub struct Day2;
+
+impl Solution for Day2 {
+    const DAY: u8 = 2;
+    const VARIANTS: &'static [Variant] = &[
+        Variant::new(1, "DirectIterator", raw_input, |raw| {
+            count_safe_reports_direct(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(1, "Naïve", parse_input::<Self>, |parsed| {
+            count_safe_reports(input::<Vec<Vec<u32>>>(parsed)).into_answer()
+        }),
+        Variant::new(1, "Delta", parse_input::<Self>, |parsed| {
+            count_safe_reports_delta(input::<Vec<Vec<u32>>>(parsed)).into_answer()
+        }),
+        Variant::new(1, "Iterator", parse_input::<Self>, |parsed| {
+            count_safe_reports_iterator(input::<Vec<Vec<u32>>>(parsed)).into_answer()
+        }),
+        Variant::new(2, "DirectIterator", raw_input, |raw| {
+            count_safe_reports_damped_direct(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(2, "Delta", parse_input::<Self>, |parsed| {
+            count_safe_reports_damped(input::<Vec<Vec<u32>>>(parsed)).into_answer()
+        }),
+        Variant::new(2, "Iterator", parse_input::<Self>, |parsed| {
+            count_safe_reports_damped_iterator(input::<Vec<Vec<u32>>>(parsed)).into_answer()
+        }),
+        Variant::new(2, "SinglePass", parse_input::<Self>, |parsed| {
+            count_safe_reports_damped_single_pass(input::<Vec<Vec<u32>>>(parsed)).into_answer()
+        }),
+    ];
+    type Input = Vec<Vec<u32>>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        try_input_generator(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(count_safe_reports_iterator(input) as Answer)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(count_safe_reports_damped_iterator(input) as Answer)
+    }
+}
 
 #[aoc_generator(day2)]
 pub fn input_generator(input: &str) -> Vec<Vec<u32>> {
-    input
+    try_input_generator(input).unwrap_or_else(|error| panic!("{error}"))
+}
+
+pub fn try_input_generator(input: &str) -> Result<Vec<Vec<u32>>, ParseError> {
+    normalise(input)
         .lines()
-        .map(|line| {
-            line.split(' ')
-                .map(|val| val.trim().parse::<u32>().unwrap())
-                .collect::<Vec<u32>>()
+        .enumerate()
+        .map(|(line_index, line)| {
+            line.split_whitespace()
+                .map(|val| parse_number::<u32>(2, line_index, line, val))
+                .collect::<Result<Vec<u32>, ParseError>>()
         })
         .collect()
 }
@@ -29,160 +95,665 @@ enum Direction {
     Decreasing,
 }
 
+impl Direction {
+    fn signum(self) -> i64 {
+        match self {
+            Direction::Increasing => 1,
+            Direction::Decreasing => -1,
+        }
+    }
+}
+
+/// Which way the levels of a safe report may go.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Directions {
+    Increasing,
+    Decreasing,
+    /// all increasing or all decreasing
+    Either,
+    /// all non-decreasing or all non-increasing, equal levels are only
+    /// allowed if the step range contains 0
+    NonStrict,
+}
+
+/// What makes a report safe: every step between adjacent levels has a size
+/// within `steps` and goes the same way, as allowed by `directions`. The
+/// damped counters may remove up to `dampener` levels, the others look at
+/// the reports as they are.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SafetyPolicy {
+    pub steps: RangeInclusive<u32>,
+    pub directions: Directions,
+    pub dampener: usize,
+}
+
+impl SafetyPolicy {
+    /// The rules of the puzzle, with a Problem Dampener removing one level.
+    pub const PUZZLE: SafetyPolicy = SafetyPolicy {
+        steps: 1..=3,
+        directions: Directions::Either,
+        dampener: 1,
+    };
+
+    /// The direction every report has to go in, if only one is allowed.
+    fn required_direction(&self) -> Option<Direction> {
+        match self.directions {
+            Directions::Increasing => Some(Direction::Increasing),
+            Directions::Decreasing => Some(Direction::Decreasing),
+            Directions::Either | Directions::NonStrict => None,
+        }
+    }
+
+    fn is_strict(&self) -> bool {
+        self.directions != Directions::NonStrict
+    }
+
+    fn directions(&self) -> &'static [Direction] {
+        match self.directions {
+            Directions::Increasing => &[Direction::Increasing],
+            Directions::Decreasing => &[Direction::Decreasing],
+            Directions::Either | Directions::NonStrict => {
+                &[Direction::Increasing, Direction::Decreasing]
+            }
+        }
+    }
+
+    /// Whether going from `from` to `to` is allowed in a report going in
+    /// `direction`.
+    fn allows(&self, direction: Direction, from: u32, to: u32) -> bool {
+        let delta = match direction {
+            Direction::Increasing => to as i64 - from as i64,
+            Direction::Decreasing => from as i64 - to as i64,
+        };
+        (delta > 0 || (delta == 0 && !self.is_strict()))
+            && self.steps.contains(&(delta.unsigned_abs() as u32))
+    }
+}
+
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        Self::PUZZLE
+    }
+}
+
 #[aoc(day2, part1, Naïve)]
 pub fn count_safe_reports(input: &[Vec<u32>]) -> u32 {
+    count_safe_reports_with_policy(input, &SafetyPolicy::PUZZLE)
+}
+
+pub fn count_safe_reports_with_policy(input: &[Vec<u32>], policy: &SafetyPolicy) -> u32 {
     input
         .iter()
-        .filter(|report| {
-            let mut report = report.iter().peekable();
-            let mut is_safe = true;
-            let mut direction = None;
-
-            while is_safe {
-                if let (Some(current), Some(next)) = (report.next(), report.peek()) {
-                    if current.abs_diff(**next) > 3 {
-                        is_safe = false;
-                        break;
-                    }
-                    let last_direction = direction;
-                    match current.cmp(*next) {
-                        Ordering::Equal => {
-                            is_safe = false;
-                            break;
-                        }
-                        Ordering::Greater => direction = Some(Direction::Decreasing),
-                        Ordering::Less => direction = Some(Direction::Increasing),
-                    }
-                    if last_direction
-                        .is_some_and(|last_direction| Some(last_direction) != direction)
-                    {
+        .filter(|report| is_safe_naive(report, policy))
+        .count() as u32
+}
+
+fn is_safe_naive(report: &[u32], policy: &SafetyPolicy) -> bool {
+    let mut report = report.iter().peekable();
+    let mut is_safe = true;
+    let mut direction = policy.required_direction();
+
+    while is_safe {
+        if let (Some(current), Some(next)) = (report.next(), report.peek()) {
+            if !policy.steps.contains(&current.abs_diff(**next)) {
+                is_safe = false;
+                break;
+            }
+            let last_direction = direction;
+            match current.cmp(*next) {
+                Ordering::Equal => {
+                    if policy.is_strict() {
                         is_safe = false;
                         break;
                     }
-                } else {
-                    break;
                 }
+                Ordering::Greater => direction = Some(Direction::Decreasing),
+                Ordering::Less => direction = Some(Direction::Increasing),
             }
-            is_safe
-        })
-        .count() as u32
+            if last_direction.is_some_and(|last_direction| Some(last_direction) != direction) {
+                is_safe = false;
+                break;
+            }
+        } else {
+            break;
+        }
+    }
+    is_safe
 }
 
 #[aoc(day2, part1, Delta)]
 pub fn count_safe_reports_delta(input: &[Vec<u32>]) -> u32 {
+    count_safe_reports_delta_with_policy(input, &SafetyPolicy::PUZZLE)
+}
+
+pub fn count_safe_reports_delta_with_policy(input: &[Vec<u32>], policy: &SafetyPolicy) -> u32 {
     input
         .iter()
-        .filter(|report| {
-            let mut last_delta_signum = None;
-            for data in report.windows(2) {
-                let new_delta = data[1] as i64 - data[0] as i64;
-                let new_delta_signum = new_delta.signum();
-
-                if last_delta_signum
-                    .is_some_and(|last_delta_signum| last_delta_signum != new_delta_signum)
-                {
-                    return false;
-                }
+        .filter(|report| is_safe_delta(report, policy))
+        .count() as u32
+}
 
-                if !(1..=3).contains(&new_delta.abs()) {
-                    return false;
-                }
+fn is_safe_delta(report: &[u32], policy: &SafetyPolicy) -> bool {
+    let mut last_delta_signum = policy.required_direction().map(Direction::signum);
+    for data in report.windows(2) {
+        let new_delta = data[1] as i64 - data[0] as i64;
+        let new_delta_signum = new_delta.signum();
+
+        if new_delta_signum == 0 && policy.is_strict() {
+            return false;
+        }
 
-                last_delta_signum = Some(new_delta_signum);
+        // equal levels keep the direction of the steps before
+        if new_delta_signum != 0 {
+            if last_delta_signum
+                .is_some_and(|last_delta_signum| last_delta_signum != new_delta_signum)
+            {
+                return false;
             }
-            true
-        })
-        .count() as u32
+            last_delta_signum = Some(new_delta_signum);
+        }
+
+        if !policy.steps.contains(&(new_delta.unsigned_abs() as u32)) {
+            return false;
+        }
+    }
+    true
 }
 
 #[aoc(day2, part1, Iterator)]
 pub fn count_safe_reports_iterator(input: &[Vec<u32>]) -> u32 {
+    count_safe_reports_iterator_with_policy(input, &SafetyPolicy::PUZZLE)
+}
+
+pub fn count_safe_reports_iterator_with_policy(input: &[Vec<u32>], policy: &SafetyPolicy) -> u32 {
     input
         .iter()
-        .filter(|report| {
-            report
-                .windows(2)
-                .map(|data| data[1] as i64 - data[0] as i64)
-                .try_fold(None, |last_signum, curr_delta| {
-                    let curr_signum = curr_delta.signum();
-                    if last_signum.is_some_and(|last_signum| last_signum != curr_signum) {
-                        return Err(());
-                    }
+        .filter(|report| is_safe_iterator(report, policy))
+        .count() as u32
+}
 
-                    if !(1..=3).contains(&curr_delta.abs()) {
-                        return Err(());
-                    }
-                    Ok(Some(curr_signum))
+fn is_safe_iterator(report: &[u32], policy: &SafetyPolicy) -> bool {
+    report
+        .windows(2)
+        .map(|data| data[1] as i64 - data[0] as i64)
+        .try_fold(
+            policy.required_direction().map(Direction::signum),
+            |last_signum, curr_delta| {
+                let curr_signum = curr_delta.signum();
+                if curr_signum == 0 && policy.is_strict() {
+                    return Err(());
+                }
+                if curr_signum != 0
+                    && last_signum.is_some_and(|last_signum| last_signum != curr_signum)
+                {
+                    return Err(());
+                }
+
+                if !policy.steps.contains(&(curr_delta.unsigned_abs() as u32)) {
+                    return Err(());
+                }
+                Ok(if curr_signum == 0 {
+                    last_signum
+                } else {
+                    Some(curr_signum)
                 })
-                .is_ok()
-        })
-        .count() as u32
+            },
+        )
+        .is_ok()
 }
 
 #[aoc(day2, part2, Delta)]
 pub fn count_safe_reports_damped(input: &[Vec<u32>]) -> u32 {
+    count_safe_reports_damped_with_policy(input, &SafetyPolicy::PUZZLE)
+}
+
+pub fn count_safe_reports_damped_with_policy(input: &[Vec<u32>], policy: &SafetyPolicy) -> u32 {
     input
         .iter()
-        .filter(|report| {
-            if count_safe_reports_delta(&[(**report).clone()]) == 1 {
-                true
-            } else {
-                let single_report = report.iter();
-                for n in 0..report.len() {
-                    // create an iterator without the nth element
-                    if count_safe_reports_delta(&[single_report
-                        .clone()
-                        .enumerate()
-                        .filter(move |(i, _)| *i != n)
-                        .map(|(_, x)| *x)
-                        .collect::<Vec<_>>()])
-                        == 1
-                    {
-                        return true;
-                    }
-                }
-                false
-            }
-        })
+        .filter(|report| is_safe_removing(report, policy.dampener, policy, is_safe_delta))
         .count() as u32
 }
+
 #[aoc(day2, part2, Iterator)]
 pub fn count_safe_reports_damped_iterator(input: &[Vec<u32>]) -> u32 {
+    count_safe_reports_damped_iterator_with_policy(input, &SafetyPolicy::PUZZLE)
+}
+
+pub fn count_safe_reports_damped_iterator_with_policy(
+    input: &[Vec<u32>],
+    policy: &SafetyPolicy,
+) -> u32 {
     input
         .iter()
-        .filter(|report| {
-            if count_safe_reports_iterator(&[(**report).clone()]) == 1 {
-                true
-            } else {
-                let single_report = report.iter();
-                for n in 0..report.len() {
-                    // create an iterator without the nth element
-                    if count_safe_reports_iterator(&[single_report
-                        .clone()
-                        .enumerate()
-                        .filter(move |(i, _)| *i != n)
-                        .map(|(_, x)| *x)
-                        .collect::<Vec<_>>()])
-                        == 1
+        .filter(|report| is_safe_removing(report, policy.dampener, policy, is_safe_iterator))
+        .count() as u32
+}
+
+/// Whether `report` is safe according to `is_safe`, after removing at most
+/// `removals` levels, trying every level to remove.
+fn is_safe_removing(
+    report: &[u32],
+    removals: usize,
+    policy: &SafetyPolicy,
+    is_safe: fn(&[u32], &SafetyPolicy) -> bool,
+) -> bool {
+    if is_safe(report, policy) {
+        return true;
+    }
+    if removals == 0 {
+        return false;
+    }
+    (0..report.len()).any(|n| {
+        // the report without the nth level
+        let without = report
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != n)
+            .map(|(_, x)| *x)
+            .collect::<Vec<_>>();
+        is_safe_removing(&without, removals - 1, policy, is_safe)
+    })
+}
+
+/// Whether `report` is safe after removing at most `policy.dampener` levels,
+/// in one pass and without copying it. For each direction, tracks the fewest
+/// removals for a safe chain of levels ending at each of the last
+/// `dampener + 1` levels, in `window`.
+fn is_safe_damped(report: &[u32], policy: &SafetyPolicy, window: &mut [usize]) -> bool {
+    let budget = policy.dampener;
+    if report.len() <= budget + 1 {
+        return true;
+    }
+    policy.directions().iter().any(|&direction| {
+        for i in 0..report.len() {
+            // start the chain here, removing everything before
+            let mut fewest = i;
+            for j in i.saturating_sub(budget + 1)..i {
+                let removals = window[j % (budget + 1)] + (i - j - 1);
+                if removals < fewest && policy.allows(direction, report[j], report[i]) {
+                    fewest = removals;
+                }
+            }
+            window[i % (budget + 1)] = fewest;
+        }
+        // end the chain at one of the last levels, removing the rest
+        (report.len() - budget - 1..report.len())
+            .any(|i| window[i % (budget + 1)] + (report.len() - 1 - i) <= budget)
+    })
+}
+
+#[aoc(day2, part2, SinglePass)]
+pub fn count_safe_reports_damped_single_pass(input: &[Vec<u32>]) -> u32 {
+    count_safe_reports_damped_single_pass_with_policy(input, &SafetyPolicy::PUZZLE)
+}
+
+pub fn count_safe_reports_damped_single_pass_with_policy(
+    input: &[Vec<u32>],
+    policy: &SafetyPolicy,
+) -> u32 {
+    let mut window = vec![0; policy.dampener + 1];
+    input
+        .iter()
+        .filter(|report| is_safe_damped(report, policy, &mut window))
+        .count() as u32
+}
+
+/// The indices of a longest subsequence of `report` that is safe under
+/// `policy`. Quadratic in the length of the report.
+fn safe_levels(report: &[u32], policy: &SafetyPolicy) -> Vec<usize> {
+    policy
+        .directions()
+        .iter()
+        .map(|&direction| {
+            // longest safe subsequence ending at each level, and the level
+            // before it
+            let mut longest = vec![(1, None); report.len()];
+            for i in 0..report.len() {
+                for j in 0..i {
+                    if longest[j].0 + 1 > longest[i].0
+                        && policy.allows(direction, report[j], report[i])
                     {
-                        return true;
+                        longest[i] = (longest[j].0 + 1, Some(j));
                     }
                 }
-                false
             }
+            let mut levels = Vec::new();
+            let mut last = (0..report.len()).max_by_key(|&i| (longest[i].0, Reverse(i)));
+            while let Some(i) = last {
+                levels.push(i);
+                last = longest[i].1;
+            }
+            levels.reverse();
+            levels
         })
+        .max_by_key(Vec::len)
+        .unwrap_or_default()
+}
+
+/// The fewest levels to remove from `report` for it to become safe: the
+/// levels not in the longest subsequence safe under `policy`. Quadratic in
+/// the length of the report.
+pub fn min_removals(report: &[u32], policy: &SafetyPolicy) -> usize {
+    report.len() - safe_levels(report, policy).len()
+}
+
+/// Reports that become safe after removing at most `policy.dampener` levels,
+/// for any dampener.
+pub fn count_safe_reports_with_removals(input: &[Vec<u32>], policy: &SafetyPolicy) -> u32 {
+    input
+        .iter()
+        .filter(|report| min_removals(report, policy) <= policy.dampener)
         .count() as u32
 }
 
+/// Why a report is unsafe.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+pub enum Failure {
+    StepTooLarge,
+    /// a step smaller than the policy allows, but not zero
+    StepTooSmall,
+    ZeroStep,
+    /// a step against the direction of the steps before, or the one the
+    /// policy requires
+    DirectionFlip,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Failure::StepTooLarge => "step too large",
+            Failure::StepTooSmall => "step too small",
+            Failure::ZeroStep => "zero step",
+            Failure::DirectionFlip => "direction flip",
+        })
+    }
+}
+
+/// The first offending step of a report: the indices of its two levels.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Violation {
+    pub levels: (usize, usize),
+    pub failure: Failure,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Diagnosis {
+    Safe,
+    /// unsafe as is, but safe after removing the levels at `removed`
+    Dampened {
+        violation: Violation,
+        removed: Vec<usize>,
+    },
+    Unsafe(Violation),
+}
+
+/// The first step of `report` breaking `policy`, if any.
+fn first_violation(report: &[u32], policy: &SafetyPolicy) -> Option<Violation> {
+    let mut direction = policy.required_direction();
+    (1..report.len()).find_map(|i| {
+        let failure = |failure| {
+            Some(Violation {
+                levels: (i - 1, i),
+                failure,
+            })
+        };
+        let (from, to) = (report[i - 1], report[i]);
+        let step = from.abs_diff(to);
+        if step == 0 {
+            let allowed = policy.directions == Directions::NonStrict && policy.steps.contains(&0);
+            return if allowed {
+                None
+            } else {
+                failure(Failure::ZeroStep)
+            };
+        }
+        let step_direction = if to > from {
+            Direction::Increasing
+        } else {
+            Direction::Decreasing
+        };
+        if *direction.get_or_insert(step_direction) != step_direction {
+            failure(Failure::DirectionFlip)
+        } else if step > *policy.steps.end() {
+            failure(Failure::StepTooLarge)
+        } else if step < *policy.steps.start() {
+            failure(Failure::StepTooSmall)
+        } else {
+            None
+        }
+    })
+}
+
+/// Why `report` is unsafe under `policy`, and which levels the dampener
+/// removes if that makes it safe.
+pub fn diagnose(report: &[u32], policy: &SafetyPolicy) -> Diagnosis {
+    let Some(violation) = first_violation(report, policy) else {
+        return Diagnosis::Safe;
+    };
+    let kept = safe_levels(report, policy);
+    if report.len() - kept.len() <= policy.dampener {
+        let removed = (0..report.len())
+            .filter(|i| kept.binary_search(i).is_err())
+            .collect();
+        Diagnosis::Dampened { violation, removed }
+    } else {
+        Diagnosis::Unsafe(violation)
+    }
+}
+
+/// How many reports are safe, and why the others are not, by the failure of
+/// their first violation.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct Summary {
+    pub safe: usize,
+    pub dampened: BTreeMap<Failure, usize>,
+    pub unsafe_reports: BTreeMap<Failure, usize>,
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let group = |failures: &BTreeMap<Failure, usize>| {
+            let total = failures.values().sum::<usize>();
+            if total == 0 {
+                return total.to_string();
+            }
+            let reasons = failures
+                .iter()
+                .map(|(failure, count)| format!("{failure}: {count}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{total} ({reasons})")
+        };
+        writeln!(f, "safe: {}", self.safe)?;
+        writeln!(f, "dampened: {}", group(&self.dampened))?;
+        write!(f, "unsafe: {}", group(&self.unsafe_reports))
+    }
+}
+
+pub fn summarise(input: &[Vec<u32>], policy: &SafetyPolicy) -> Summary {
+    let mut summary = Summary::default();
+    for report in input {
+        match diagnose(report, policy) {
+            Diagnosis::Safe => summary.safe += 1,
+            Diagnosis::Dampened { violation, .. } => {
+                *summary.dampened.entry(violation.failure).or_default() += 1
+            }
+            Diagnosis::Unsafe(violation) => {
+                *summary.unsafe_reports.entry(violation.failure).or_default() += 1
+            }
+        }
+    }
+    summary
+}
+
+/// Reports read, and how many of them are safe as they are and with the
+/// dampener.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct Counts {
+    pub reports: u64,
+    pub safe: u64,
+    pub damped: u64,
+}
+
+impl AddAssign for Counts {
+    fn add_assign(&mut self, other: Self) {
+        self.reports += other.reports;
+        self.safe += other.safe;
+        self.damped += other.damped;
+    }
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(std::io::Error),
+    Parse(ParseError),
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Io(error) => write!(f, "failed to read reports: {error}"),
+            StreamError::Parse(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StreamError::Io(error) => Some(error),
+            StreamError::Parse(error) => Some(error),
+        }
+    }
+}
+
+impl From<std::io::Error> for StreamError {
+    fn from(error: std::io::Error) -> Self {
+        StreamError::Io(error)
+    }
+}
+
+impl From<ParseError> for StreamError {
+    fn from(error: ParseError) -> Self {
+        StreamError::Parse(error)
+    }
+}
+
+fn count_batch(batch: &[Vec<u32>], policy: &SafetyPolicy) -> Counts {
+    batch
+        .par_iter()
+        .map_init(
+            || vec![0; policy.dampener + 1],
+            |window, report| Counts {
+                reports: 1,
+                safe: first_violation(report, policy).is_none() as u64,
+                damped: is_safe_damped(report, policy, window) as u64,
+            },
+        )
+        .reduce(Counts::default, |mut a, b| {
+            a += b;
+            a
+        })
+}
+
+/// Counts the reports of `reader` line by line, checking `batch_size` of
+/// them at a time in parallel, so only one batch is ever in memory. Lines
+/// are cleaned up like `normalise` does and give the same counts as
+/// `try_input_generator` on the whole input.
+pub fn count_safe_reports_streaming(
+    mut reader: impl BufRead,
+    policy: &SafetyPolicy,
+    batch_size: usize,
+) -> Result<Counts, StreamError> {
+    let batch_size = batch_size.max(1);
+    let mut counts = Counts::default();
+    let mut batch = Vec::with_capacity(batch_size);
+    // blank lines only count as (empty, so safe) reports if more reports
+    // follow
+    let mut blank_lines = 0;
+    let mut line_index = 0;
+    let mut buffer = String::new();
+    loop {
+        buffer.clear();
+        if reader.read_line(&mut buffer)? == 0 {
+            break;
+        }
+        let text = buffer.strip_suffix('\n').unwrap_or(&buffer);
+        let mut text = text.strip_suffix('\r').unwrap_or(text);
+        if line_index == 0 {
+            text = text.strip_prefix('\u{feff}').unwrap_or(text);
+        }
+        // a lone CR ends a line too
+        for line in text.split('\r') {
+            let line = line.trim_end();
+            line_index += 1;
+            if line.is_empty() {
+                blank_lines += 1;
+                continue;
+            }
+            counts += Counts {
+                reports: blank_lines,
+                safe: blank_lines,
+                damped: blank_lines,
+            };
+            blank_lines = 0;
+            batch.push(
+                line.split_whitespace()
+                    .map(|val| parse_number::<u32>(2, line_index - 1, line, val))
+                    .collect::<Result<Vec<u32>, ParseError>>()?,
+            );
+            if batch.len() >= batch_size {
+                counts += count_batch(&batch, policy);
+                batch.clear();
+            }
+        }
+    }
+    counts += count_batch(&batch, policy);
+    Ok(counts)
+}
+
 #[cfg(test)]
 mod test {
 
     use super::{
-        count_safe_reports, count_safe_reports_damped, count_safe_reports_delta,
-        count_safe_reports_iterator,
+        count_safe_reports, count_safe_reports_damped, count_safe_reports_damped_iterator,
+        count_safe_reports_damped_iterator_with_policy, count_safe_reports_damped_single_pass,
+        count_safe_reports_damped_single_pass_with_policy, count_safe_reports_damped_with_policy,
+        count_safe_reports_delta, count_safe_reports_delta_with_policy,
+        count_safe_reports_iterator, count_safe_reports_iterator_with_policy,
+        count_safe_reports_streaming, count_safe_reports_with_policy,
+        count_safe_reports_with_removals, diagnose, min_removals, summarise, try_input_generator,
+        Counts, Diagnosis, Directions, Failure, SafetyPolicy, StreamError, Violation,
     };
+    use crate::random::{reports, ReportOptions, Rng};
+    use std::error::Error;
+    use std::io::Cursor;
+
+    const PUZZLE: SafetyPolicy = SafetyPolicy::PUZZLE;
+
+    fn damped(dampener: usize) -> SafetyPolicy {
+        SafetyPolicy {
+            dampener,
+            ..SafetyPolicy::PUZZLE
+        }
+    }
 
     #[test]
+    fn test_try_input_generator() {
+        assert_eq!(
+            try_input_generator("7 6 4\n1 2"),
+            Ok(vec![vec![7, 6, 4], vec![1, 2]])
+        );
+        assert_eq!(
+            try_input_generator("7  6 4\r\n1 2\r\n"),
+            Ok(vec![vec![7, 6, 4], vec![1, 2]])
+        );
+        let error = try_input_generator("7 6 4\n1 2 -3").unwrap_err();
+        assert_eq!((error.day, error.line, error.column), (2, 2, 5));
+    }
+
+    #[test]
+    #[allow(clippy::cloned_ref_to_slice_refs)]
     fn sample1() {
         let data = vec![
             (vec![7, 6, 4, 2, 1], true, true),
@@ -220,6 +791,290 @@ mod test {
                 "damped test for {:?} failed.",
                 sample
             );
+            assert_eq!(
+                count_safe_reports_damped_single_pass(&[sample.clone()]),
+                if expected_damped { 1 } else { 0 },
+                "single pass damped test for {:?} failed.",
+                sample
+            );
+        }
+    }
+
+    #[test]
+    fn test_single_pass_agrees() {
+        // every report of up to 5 levels between 0 and 6
+        let mut reports = vec![vec![]];
+        for length in 1..=5u32 {
+            for mut code in 0..7u32.pow(length) {
+                reports.push(
+                    (0..length)
+                        .map(|_| {
+                            let level = code % 7;
+                            code /= 7;
+                            level
+                        })
+                        .collect(),
+                );
+            }
+        }
+        let policies = [
+            PUZZLE,
+            damped(2),
+            SafetyPolicy {
+                steps: 0..=2,
+                directions: Directions::NonStrict,
+                dampener: 1,
+            },
+            SafetyPolicy {
+                directions: Directions::Decreasing,
+                ..damped(2)
+            },
+        ];
+        for report in &reports {
+            let report = std::slice::from_ref(report);
+            for policy in &policies {
+                let safe = count_safe_reports_with_removals(
+                    report,
+                    &SafetyPolicy {
+                        dampener: 0,
+                        ..policy.clone()
+                    },
+                );
+                for count in [
+                    count_safe_reports_with_policy,
+                    count_safe_reports_delta_with_policy,
+                    count_safe_reports_iterator_with_policy,
+                ] {
+                    assert_eq!(count(report, policy), safe, "{report:?} {policy:?}");
+                }
+                let safe = count_safe_reports_with_removals(report, policy);
+                for count in [
+                    count_safe_reports_damped_with_policy,
+                    count_safe_reports_damped_iterator_with_policy,
+                    count_safe_reports_damped_single_pass_with_policy,
+                ] {
+                    assert_eq!(count(report, policy), safe, "{report:?} {policy:?}");
+                }
+            }
+            assert_eq!(
+                count_safe_reports_damped_single_pass(report),
+                count_safe_reports_damped_iterator(report),
+                "{report:?}"
+            );
+            assert_eq!(
+                count_safe_reports_with_removals(report, &PUZZLE),
+                count_safe_reports_damped_iterator(report),
+                "{report:?}"
+            );
+            assert_eq!(
+                count_safe_reports_damped_single_pass_with_policy(report, &damped(2)),
+                count_safe_reports_with_removals(report, &damped(2)),
+                "{report:?}"
+            );
+            let diagnosis = diagnose(&report[0], &PUZZLE);
+            assert_eq!(
+                diagnosis == Diagnosis::Safe,
+                count_safe_reports_iterator(report) == 1,
+                "{report:?}"
+            );
+            assert_eq!(
+                matches!(diagnosis, Diagnosis::Safe | Diagnosis::Dampened { .. }),
+                count_safe_reports_damped_iterator(report) == 1,
+                "{report:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_min_removals() {
+        assert_eq!(min_removals(&[], &PUZZLE), 0);
+        assert_eq!(min_removals(&[7, 6, 4, 2, 1], &PUZZLE), 0);
+        assert_eq!(min_removals(&[1, 3, 2, 4, 5], &PUZZLE), 1);
+        assert_eq!(min_removals(&[1, 2, 7, 8, 9], &PUZZLE), 2);
+        assert_eq!(min_removals(&[5, 5, 5, 5], &PUZZLE), 3);
+        assert_eq!(min_removals(&[1, 9, 2, 9, 3, 4], &PUZZLE), 2);
+
+        let reports = vec![
+            vec![7, 6, 4, 2, 1],
+            vec![1, 2, 7, 8, 9],
+            vec![9, 7, 6, 2, 1],
+            vec![1, 3, 2, 4, 5],
+            vec![8, 6, 4, 4, 1],
+            vec![1, 3, 6, 7, 9],
+        ];
+        assert_eq!(
+            count_safe_reports_with_removals(&reports, &damped(0)),
+            count_safe_reports_iterator(&reports)
+        );
+        assert_eq!(
+            count_safe_reports_with_removals(&reports, &PUZZLE),
+            count_safe_reports_damped_iterator(&reports)
+        );
+        assert_eq!(count_safe_reports_with_removals(&reports, &damped(2)), 6);
+    }
+
+    #[test]
+    fn test_safety_policy() {
+        let reports = vec![
+            vec![1, 2, 2, 3],
+            vec![5, 4, 4, 4, 3],
+            vec![1, 5, 9],
+            vec![1, 2, 3, 1],
+            vec![9, 8, 1, 7],
+        ];
+        let policies = [
+            (PUZZLE, 0, 3),
+            (
+                SafetyPolicy {
+                    directions: Directions::Increasing,
+                    ..PUZZLE
+                },
+                0,
+                2,
+            ),
+            (
+                SafetyPolicy {
+                    directions: Directions::Decreasing,
+                    ..PUZZLE
+                },
+                0,
+                1,
+            ),
+            (
+                SafetyPolicy {
+                    steps: 0..=3,
+                    directions: Directions::NonStrict,
+                    dampener: 0,
+                },
+                2,
+                2,
+            ),
+            (
+                SafetyPolicy {
+                    steps: 1..=4,
+                    directions: Directions::Either,
+                    dampener: 2,
+                },
+                1,
+                5,
+            ),
+        ];
+        type Counter = fn(&[Vec<u32>], &SafetyPolicy) -> u32;
+        let normal: [Counter; 3] = [
+            count_safe_reports_with_policy,
+            count_safe_reports_delta_with_policy,
+            count_safe_reports_iterator_with_policy,
+        ];
+        let damped: [Counter; 4] = [
+            count_safe_reports_damped_with_policy,
+            count_safe_reports_damped_iterator_with_policy,
+            count_safe_reports_damped_single_pass_with_policy,
+            count_safe_reports_with_removals,
+        ];
+        for (policy, expected_normal, expected_damped) in policies {
+            for count in normal {
+                assert_eq!(count(&reports, &policy), expected_normal, "{policy:?}");
+            }
+            for count in damped {
+                assert_eq!(count(&reports, &policy), expected_damped, "{policy:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let violation = |levels, failure| Violation { levels, failure };
+        assert_eq!(diagnose(&[7, 6, 4, 2, 1], &PUZZLE), Diagnosis::Safe);
+        assert_eq!(
+            diagnose(&[1, 2, 7, 8, 9], &PUZZLE),
+            Diagnosis::Unsafe(violation((1, 2), Failure::StepTooLarge))
+        );
+        assert_eq!(
+            diagnose(&[1, 3, 2, 4, 5], &PUZZLE),
+            Diagnosis::Dampened {
+                violation: violation((1, 2), Failure::DirectionFlip),
+                removed: vec![2],
+            }
+        );
+        assert_eq!(
+            diagnose(&[8, 6, 4, 4, 1], &PUZZLE),
+            Diagnosis::Dampened {
+                violation: violation((2, 3), Failure::ZeroStep),
+                removed: vec![3],
+            }
+        );
+        assert_eq!(
+            diagnose(&[8, 6, 4, 4, 1], &damped(0)),
+            Diagnosis::Unsafe(violation((2, 3), Failure::ZeroStep))
+        );
+        let increasing = SafetyPolicy {
+            steps: 2..=3,
+            directions: Directions::Increasing,
+            dampener: 0,
+        };
+        assert_eq!(
+            diagnose(&[3, 1], &increasing),
+            Diagnosis::Unsafe(violation((0, 1), Failure::DirectionFlip))
+        );
+        assert_eq!(
+            diagnose(&[1, 3, 4], &increasing),
+            Diagnosis::Unsafe(violation((1, 2), Failure::StepTooSmall))
+        );
+    }
+
+    #[test]
+    fn test_summarise() {
+        let reports =
+            try_input_generator("7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9")
+                .unwrap();
+        let summary = summarise(&reports, &PUZZLE);
+        assert_eq!(summary.safe, 2);
+        assert_eq!(
+            summary.to_string(),
+            "safe: 2\n\
+             dampened: 2 (zero step: 1, direction flip: 1)\n\
+             unsafe: 2 (step too large: 2)"
+        );
+    }
+
+    #[test]
+    fn test_streaming() {
+        let sample = "7 6 4 2 1\r\n1 2 7 8 9\r\n\r\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9\n\n";
+        for batch_size in [0, 1, 2, 100] {
+            let counts =
+                count_safe_reports_streaming(Cursor::new(sample), &PUZZLE, batch_size).unwrap();
+            assert_eq!(
+                counts,
+                Counts {
+                    reports: 7,
+                    safe: 3,
+                    damped: 5
+                }
+            );
         }
+
+        let mut rng = Rng::new(2);
+        let input = reports(&mut rng, &ReportOptions::default());
+        let parsed = try_input_generator(&input).unwrap();
+        let policy = damped(2);
+        assert_eq!(
+            count_safe_reports_streaming(Cursor::new(&input), &policy, 7).unwrap(),
+            Counts {
+                reports: parsed.len() as u64,
+                safe: count_safe_reports_iterator_with_policy(&parsed, &policy) as u64,
+                damped: count_safe_reports_with_removals(&parsed, &policy) as u64,
+            }
+        );
+
+        let error =
+            count_safe_reports_streaming(Cursor::new("1 2\r\n\r\n3 x"), &PUZZLE, 1).unwrap_err();
+        assert_eq!(
+            error.source().map(ToString::to_string),
+            Some(error.to_string())
+        );
+        let StreamError::Parse(error) = error else {
+            panic!("expected a parse error, found {error}");
+        };
+        assert_eq!((error.line, error.column), (3, 3));
     }
 }
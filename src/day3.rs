@@ -1,20 +1,97 @@
-use std::{iter::Peekable, str::Chars};
+use crate::error::{ParseError, SolveError};
+use crate::solution::{input, raw_input, Answer, IntoAnswer, Solution, Variant, DEFAULT_VARIANT};
+use std::fmt::Display;
+use std::io::{ErrorKind, Read};
+use std::ops::Range;
 
-#[derive(Debug)]
+/// Which operands `mul` takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserConfig {
+    pub min_digits: usize,
+    /// at most `MAX_DIGITS`, more are ignored
+    pub max_digits: usize,
+    /// whether operands may start with a `-`
+    pub signed: bool,
+}
+
+impl ParserConfig {
+    /// Digits that always fit an `i128`.
+    pub const MAX_DIGITS: usize = 38;
+
+    /// One to three digits, unsigned.
+    pub const PUZZLE: ParserConfig = ParserConfig {
+        min_digits: 1,
+        max_digits: 3,
+        signed: false,
+    };
+
+    fn max_digits(&self) -> usize {
+        self.max_digits.min(Self::MAX_DIGITS)
+    }
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self::PUZZLE
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Multiplication {
-    left: u16,
-    right: u16,
+    pub left: i128,
+    pub right: i128,
+}
+
+/// An operand being read.
+#[derive(Debug, Clone, Copy, Default)]
+struct Operand {
+    value: i128,
+    digits: usize,
+    negative: bool,
 }
+
+impl Operand {
+    fn is_valid(&self, config: &ParserConfig, new_char: char) -> bool {
+        if new_char == '-' {
+            config.signed && self.digits == 0 && !self.negative
+        } else {
+            new_char.is_ascii_digit() && self.digits < config.max_digits()
+        }
+    }
+
+    fn is_complete(&self, config: &ParserConfig) -> bool {
+        self.digits >= config.min_digits && (self.digits > 0 || !self.negative)
+    }
+
+    fn push(&mut self, new_char: char) {
+        if new_char == '-' {
+            self.negative = true;
+        } else {
+            self.value = self.value * 10 + (new_char as u8 - b'0') as i128;
+            self.digits += 1;
+        }
+    }
+
+    fn value(&self) -> i128 {
+        if self.negative {
+            -self.value
+        } else {
+            self.value
+        }
+    }
+}
+
 // the current state indicates what is expected next.
+#[derive(Debug, Clone, Copy)]
 enum MultiplicationGenerator {
-    Indicator { index: u8 },                         // mul
-    BracketLeft,                                     // (
-    FirstNumber { digits: u16 },                     // 12
-    SecondNumber { first_number: u16, digits: u16 }, // 24
+    Indicator { index: u8 },                              // mul
+    BracketLeft,                                          // (
+    FirstNumber { digits: Operand },                      // 12
+    SecondNumber { first_number: i128, digits: Operand }, // 24
     Done,
 }
 impl MultiplicationGenerator {
-    fn is_valid(&self, new_char: char) -> bool {
+    fn is_valid(&self, config: &ParserConfig, new_char: char) -> bool {
         match self {
             MultiplicationGenerator::Indicator { index } => match index {
                 0 => new_char == 'm',
@@ -23,13 +100,15 @@ impl MultiplicationGenerator {
                 _ => false,
             },
             MultiplicationGenerator::BracketLeft => new_char == '(',
-            MultiplicationGenerator::FirstNumber { digits: _ } => {
-                new_char.is_ascii_digit() || new_char == ','
+            MultiplicationGenerator::FirstNumber { digits } => {
+                digits.is_valid(config, new_char) || (new_char == ',' && digits.is_complete(config))
             }
             MultiplicationGenerator::SecondNumber {
                 first_number: _,
-                digits: _,
-            } => new_char.is_ascii_digit() || new_char == ')',
+                digits,
+            } => {
+                digits.is_valid(config, new_char) || (new_char == ')' && digits.is_complete(config))
+            }
 
             _ => false,
         }
@@ -44,16 +123,18 @@ impl MultiplicationGenerator {
                 }
             }
             MultiplicationGenerator::BracketLeft => {
-                *self = MultiplicationGenerator::FirstNumber { digits: 0 }
+                *self = MultiplicationGenerator::FirstNumber {
+                    digits: Operand::default(),
+                }
             }
             MultiplicationGenerator::FirstNumber { digits } => {
                 if new_char == ',' {
                     *self = MultiplicationGenerator::SecondNumber {
-                        first_number: *digits,
-                        digits: 0,
+                        first_number: digits.value(),
+                        digits: Operand::default(),
                     };
                 } else {
-                    *digits = *digits * 10 + (new_char as u16 - '0' as u16);
+                    digits.push(new_char);
                 }
             }
             MultiplicationGenerator::SecondNumber {
@@ -63,12 +144,12 @@ impl MultiplicationGenerator {
                 if new_char == ')' {
                     let mul = Multiplication {
                         left: *first_number,
-                        right: *digits,
+                        right: digits.value(),
                     };
                     *self = MultiplicationGenerator::Done;
                     return Some(mul);
                 } else {
-                    *digits = *digits * 10 + (new_char as u16 - '0' as u16)
+                    digits.push(new_char);
                 }
             }
             Self::Done => {}
@@ -77,118 +158,1477 @@ impl MultiplicationGenerator {
     }
 }
 
-pub fn skip_if_disabled(chars: &mut Peekable<Chars<'_>>) {
-    if let Some(peeked) = chars.peek() {
-        if *peeked == 'd' {
-            // could be a "don't". we peek
-            let mut disabler = "don't()".chars().peekable();
-            while disabler.peek() == chars.peek() {
-                if chars.next().is_none() || disabler.next().is_none() {
-                    break;
-                }
-            }
-            if disabler.peek().is_none() {
-                // we are disabled. search until we have a "do"
-                while let Some(peeked) = chars.peek() {
-                    if *peeked == 'd' {
-                        let mut enabler = "do()".chars().peekable();
-                        while enabler.peek() == chars.peek() {
-                            if chars.next().is_none() || enabler.next().is_none() {
-                                break;
-                            }
-                        }
-                        if enabler.peek().is_none() {
-                            break;
-                        }
-                    } else {
-                        chars.next();
-                    }
+/// What sums of products are accumulated in.
+pub trait Accumulator: Copy + Default {
+    /// `self` plus `left` times `right`, unless that doesn't fit `Self`.
+    fn mul_add(self, left: i128, right: i128) -> Option<Self>;
+}
+
+/// Whether `left` times `right` is negative, and its size unless that
+/// doesn't fit a `u128`.
+fn product(left: i128, right: i128) -> Option<(bool, u128)> {
+    let size = left.unsigned_abs().checked_mul(right.unsigned_abs())?;
+    Some(((left < 0) != (right < 0) && size != 0, size))
+}
+
+macro_rules! accumulator {
+    ($($total:ty),*) => {
+        $(impl Accumulator for $total {
+            fn mul_add(self, left: i128, right: i128) -> Option<Self> {
+                match product(left, right)? {
+                    (false, size) => self.checked_add(size.try_into().ok()?),
+                    // a negative product only fits if it is 0
+                    (true, _) => None,
+                }
+            }
+        })*
+    };
+    ($($total:ty: $size:ty),*) => {
+        $(impl Accumulator for $total {
+            fn mul_add(self, left: i128, right: i128) -> Option<Self> {
+                let (negative, size) = product(left, right)?;
+                let size = <$size>::try_from(size).ok()?;
+                if negative {
+                    self.checked_sub_unsigned(size)
+                } else {
+                    self.checked_add_unsigned(size)
                 }
             }
+        })*
+    };
+}
+
+accumulator!(u32, u64, u128);
+accumulator!(i64: u64, i128: u128);
+
+/// A multiplication that doesn't fit the accumulator, the `index`-th one
+/// counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow {
+    pub index: usize,
+    pub multiplication: Multiplication,
+}
+
+impl std::error::Error for Overflow {}
+
+impl From<Overflow> for SolveError {
+    fn from(overflow: Overflow) -> Self {
+        SolveError::Overflow(overflow.to_string())
+    }
+}
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "multiplication {} (mul({},{})) overflows the result",
+            self.index + 1,
+            self.multiplication.left,
+            self.multiplication.right
+        )
+    }
+}
+
+/// Adds the product of `multiplication`, the `index`-th one, to `result`.
+fn accumulate<A: Accumulator>(
+    result: A,
+    index: usize,
+    multiplication: Multiplication,
+) -> Result<A, Overflow> {
+    result
+        .mul_add(multiplication.left, multiplication.right)
+        .ok_or(Overflow {
+            index,
+            multiplication,
+        })
+}
+
+/// What a toggle token does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToggleAction {
+    Enable,
+    Disable,
+    /// opens a nested scope, enabled or not
+    Open(bool),
+    /// closes the innermost scope, going back to the state before it
+    Close,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Toggle {
+    pub token: &'static str,
+    pub action: ToggleAction,
+}
+
+/// The tokens enabling and disabling instructions, and whether they start
+/// enabled. If several tokens start at the same place, the shortest wins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToggleSet {
+    pub toggles: Vec<Toggle>,
+    pub initial: bool,
+}
+
+impl ToggleSet {
+    /// Always enabled.
+    pub fn none() -> Self {
+        ToggleSet {
+            toggles: Vec::new(),
+            initial: true,
         }
     }
+
+    /// `do()` and `don't()`.
+    pub fn puzzle() -> Self {
+        Self::none()
+            .with("do()", ToggleAction::Enable)
+            .with("don't()", ToggleAction::Disable)
+    }
+
+    pub fn with(mut self, token: &'static str, action: ToggleAction) -> Self {
+        self.toggles.push(Toggle { token, action });
+        self
+    }
+
+    pub fn starting(mut self, enabled: bool) -> Self {
+        self.initial = enabled;
+        self
+    }
+
+    pub fn state(&self) -> ToggleState {
+        ToggleState {
+            scopes: vec![self.initial],
+        }
+    }
+
+    /// The toggle at the start of `memory`.
+    fn toggle_at(&self, memory: &[u8]) -> Option<&Toggle> {
+        self.toggles
+            .iter()
+            .filter(|toggle| {
+                !toggle.token.is_empty() && memory.starts_with(toggle.token.as_bytes())
+            })
+            .min_by_key(|toggle| toggle.token.len())
+    }
+
+    /// Whether `bytes` could be the start of a toggle.
+    fn is_prefix(&self, bytes: &[u8]) -> bool {
+        self.toggles
+            .iter()
+            .any(|toggle| toggle.token.as_bytes().starts_with(bytes))
+    }
 }
 
-pub fn evaluate_muls(input: &str, skip_donts: bool) -> u32 {
-    let mut result = 0;
-    let mut chars = input.chars().peekable();
-    while chars.peek().is_some() {
-        if skip_donts {
-            skip_if_disabled(&mut chars);
+impl Default for ToggleSet {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+/// Whether instructions are enabled, in every open scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToggleState {
+    scopes: Vec<bool>,
+}
+
+impl ToggleState {
+    pub fn enabled(&self) -> bool {
+        *self.scopes.last().unwrap()
+    }
+
+    pub fn apply(&mut self, action: ToggleAction) {
+        match action {
+            ToggleAction::Enable => *self.scopes.last_mut().unwrap() = true,
+            ToggleAction::Disable => *self.scopes.last_mut().unwrap() = false,
+            ToggleAction::Open(enabled) => self.scopes.push(enabled),
+            // the outermost scope stays open
+            ToggleAction::Close if self.scopes.len() > 1 => {
+                self.scopes.pop();
+            }
+            ToggleAction::Close => {}
         }
-        let mut current_generator = MultiplicationGenerator::Indicator { index: 0 };
-        for new_char in chars.by_ref() {
-            if current_generator.is_valid(new_char) {
-                if let Some(new_mul) = current_generator.advance(new_char) {
-                    result += new_mul.left as u32 * new_mul.right as u32;
-                    break;
-                }
-            } else {
-                break;
+    }
+}
+
+impl Default for ToggleState {
+    fn default() -> Self {
+        ToggleSet::none().state()
+    }
+}
+
+/// The multiplication at the start of `input`, and its length.
+fn parse_multiplication(input: &str, config: &ParserConfig) -> Option<(Multiplication, usize)> {
+    let mut current_generator = MultiplicationGenerator::Indicator { index: 0 };
+    for (offset, new_char) in input.char_indices() {
+        if !current_generator.is_valid(config, new_char) {
+            return None;
+        }
+        if let Some(new_mul) = current_generator.advance(new_char) {
+            return Some((new_mul, offset + 1));
+        }
+    }
+    None
+}
+
+/// Sums the multiplications in `input` with operands as in `config` and
+/// enabled by `toggles`, reporting the first one overflowing `A`.
+///
+/// Like every evaluator, this looks for a toggle and then a multiplication
+/// at each position, whether enabled or not, and moves on by one char if
+/// neither is there.
+pub fn evaluate_checked<A: Accumulator>(
+    input: &str,
+    config: &ParserConfig,
+    toggles: &ToggleSet,
+) -> Result<A, Overflow> {
+    let mut result = A::default();
+    let mut count = 0;
+    let mut state = toggles.state();
+    let mut position = 0;
+    while let Some(new_char) = input[position..].chars().next() {
+        let rest = &input[position..];
+        if let Some(toggle) = toggles.toggle_at(rest.as_bytes()) {
+            state.apply(toggle.action);
+            position += toggle.token.len();
+        } else if let Some((new_mul, length)) = parse_multiplication(rest, config) {
+            if state.enabled() {
+                result = accumulate(result, count, new_mul)?;
+                count += 1;
             }
+            position += length;
+        } else {
+            // the next instruction may start inside a broken one
+            position += new_char.len_utf8();
         }
     }
-    result
+    Ok(result)
+}
+
+pub fn evaluate_muls(input: &str, skip_donts: bool) -> Result<u64, Overflow> {
+    let toggles = if skip_donts {
+        ToggleSet::puzzle()
+    } else {
+        ToggleSet::none()
+    };
+    evaluate_checked(input, &ParserConfig::PUZZLE, &toggles)
 }
 
 #[aoc(day3, part1)]
-pub fn evaluate_ignore_do_dont(input: &str) -> u32 {
-    let mut result = 0;
-    let mut chars = input.chars().peekable();
-    while chars.peek().is_some() {
-        let mut current_generator = MultiplicationGenerator::Indicator { index: 0 };
-        for new_char in chars.by_ref() {
-            if current_generator.is_valid(new_char) {
-                if let Some(new_mul) = current_generator.advance(new_char) {
-                    result += new_mul.left as u32 * new_mul.right as u32;
-                    break;
+pub fn evaluate_ignore_do_dont(input: &str) -> Result<u64, Overflow> {
+    evaluate_muls(input, false)
+}
+
+#[aoc(day3, part2)]
+pub fn evaluate_do_dont(input: &str) -> Result<u64, Overflow> {
+    evaluate_muls(input, true)
+}
+
+/// What instructions act on.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Machine {
+    pub toggles: ToggleState,
+    pub total: i128,
+}
+
+impl Machine {
+    pub fn enabled(&self) -> bool {
+        self.toggles.enabled()
+    }
+}
+
+/// An instruction `name(arg, ...)` with `arity` integer arguments, as in
+/// the interpreter's `ParserConfig`, and what it does: `None` if the result
+/// doesn't fit the machine. Handlers also run while the machine is disabled,
+/// and decide what that means for them.
+#[derive(Debug, Clone, Copy)]
+pub struct Handler {
+    pub name: &'static str,
+    pub arity: usize,
+    pub run: fn(&mut Machine, &[i128]) -> Option<()>,
+}
+
+impl Handler {
+    pub const MUL: Handler = Handler {
+        name: "mul",
+        arity: 2,
+        run: |machine, args| {
+            if machine.enabled() {
+                machine.total = machine.total.checked_add(args[0].checked_mul(args[1])?)?;
+            }
+            Some(())
+        },
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// index of the handler in the interpreter
+    Handler(usize),
+    Toggle(ToggleAction),
+}
+
+/// An instruction found in the memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction<'a> {
+    pub operation: Operation,
+    pub args: Vec<i128>,
+    pub text: &'a str,
+    /// byte range of `text` in the memory
+    pub span: Range<usize>,
+}
+
+/// An instruction, whether the machine was enabled when it was found and
+/// the machine after running it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Traced<'a> {
+    pub instruction: Instruction<'a>,
+    pub enabled: bool,
+    pub after: Machine,
+}
+
+/// An instruction whose result doesn't fit the machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionOverflow {
+    pub text: String,
+    /// byte range of `text` in the memory
+    pub span: Range<usize>,
+}
+
+impl std::error::Error for InstructionOverflow {}
+
+impl Display for InstructionOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at byte {} overflows the total",
+            self.text, self.span.start
+        )
+    }
+}
+
+impl From<InstructionOverflow> for SolveError {
+    fn from(overflow: InstructionOverflow) -> Self {
+        SolveError::Overflow(overflow.to_string())
+    }
+}
+
+/// A run of the interpreter, instruction by instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace<'a> {
+    /// the machine before the first instruction
+    pub start: Machine,
+    pub steps: Vec<Traced<'a>>,
+}
+
+/// Finds instructions of a set of handlers and toggles in corrupted memory
+/// and runs them. Anything else in the memory is ignored.
+#[derive(Debug, Clone, Default)]
+pub struct Interpreter {
+    pub handlers: Vec<Handler>,
+    pub toggles: ToggleSet,
+    pub config: ParserConfig,
+}
+
+impl Interpreter {
+    pub fn new(handlers: impl IntoIterator<Item = Handler>) -> Self {
+        Interpreter {
+            handlers: handlers.into_iter().collect(),
+            toggles: ToggleSet::none(),
+            config: ParserConfig::PUZZLE,
+        }
+    }
+
+    /// Only `mul`.
+    pub fn part1() -> Self {
+        Self::new([Handler::MUL])
+    }
+
+    /// `mul`, toggled by `do()` and `don't()`.
+    pub fn part2() -> Self {
+        Self::part1().with_toggles(ToggleSet::puzzle())
+    }
+
+    pub fn with(mut self, handler: Handler) -> Self {
+        self.handlers.push(handler);
+        self
+    }
+
+    pub fn with_toggles(mut self, toggles: ToggleSet) -> Self {
+        self.toggles = toggles;
+        self
+    }
+
+    pub fn with_config(mut self, config: ParserConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    fn parse_arg(&self, arg: &str) -> Option<i128> {
+        let digits = match arg.strip_prefix('-') {
+            Some(digits) if self.config.signed && !digits.is_empty() => digits,
+            Some(_) => return None,
+            None => arg,
+        };
+        if !(self.config.min_digits..=self.config.max_digits()).contains(&digits.len())
+            || !digits.bytes().all(|byte| byte.is_ascii_digit())
+        {
+            return None;
+        }
+        if digits.is_empty() {
+            return Some(0);
+        }
+        arg.parse().ok()
+    }
+
+    /// The operation, arguments and text of the instruction at the start of
+    /// `input`, if there is one. Toggles come first.
+    fn instruction_at<'a>(&self, input: &'a str) -> Option<(Operation, Vec<i128>, &'a str)> {
+        if let Some(toggle) = self.toggles.toggle_at(input.as_bytes()) {
+            let text = &input[..toggle.token.len()];
+            return Some((Operation::Toggle(toggle.action), Vec::new(), text));
+        }
+        self.handlers
+            .iter()
+            .enumerate()
+            .find_map(|(index, handler)| {
+                let rest = input.strip_prefix(handler.name)?.strip_prefix('(')?;
+                // the longest arguments there can be: signed operands and the
+                // commas between them
+                let longest = handler.arity * (self.config.max_digits() + 1)
+                    + handler.arity.saturating_sub(1);
+                let end = rest
+                    .bytes()
+                    .take(longest + 1)
+                    .position(|byte| !(byte.is_ascii_digit() || byte == b',' || byte == b'-'))?;
+                if rest.as_bytes()[end] != b')' {
+                    return None;
                 }
-            } else {
-                break;
+                let args = if end == 0 {
+                    Vec::new()
+                } else {
+                    rest[..end]
+                        .split(',')
+                        .map(|arg| self.parse_arg(arg))
+                        .collect::<Option<Vec<i128>>>()?
+                };
+                if args.len() != handler.arity {
+                    return None;
+                }
+                let length = handler.name.len() + 1 + end + 1;
+                Some((Operation::Handler(index), args, &input[..length]))
+            })
+    }
+
+    /// Every instruction in `input`, in order.
+    pub fn instructions<'s, 'a>(
+        &'s self,
+        input: &'a str,
+    ) -> impl Iterator<Item = Instruction<'a>> + use<'s, 'a> {
+        let mut position = 0;
+        std::iter::from_fn(move || {
+            while position < input.len() {
+                let rest = &input[position..];
+                if let Some((operation, args, text)) = self.instruction_at(rest) {
+                    let span = position..position + text.len();
+                    position = span.end;
+                    return Some(Instruction {
+                        operation,
+                        args,
+                        text,
+                        span,
+                    });
+                }
+                position += rest.chars().next().map_or(1, char::len_utf8);
             }
+            None
+        })
+    }
+
+    fn machine(&self) -> Machine {
+        Machine {
+            toggles: self.toggles.state(),
+            total: 0,
         }
     }
-    result
+
+    fn execute(
+        &self,
+        machine: &mut Machine,
+        instruction: &Instruction,
+    ) -> Result<(), InstructionOverflow> {
+        match instruction.operation {
+            Operation::Handler(index) => (self.handlers[index].run)(machine, &instruction.args)
+                .ok_or_else(|| InstructionOverflow {
+                    text: instruction.text.to_string(),
+                    span: instruction.span.clone(),
+                }),
+            Operation::Toggle(action) => {
+                machine.toggles.apply(action);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn run(&self, input: &str) -> Result<Machine, InstructionOverflow> {
+        let mut machine = self.machine();
+        for instruction in self.instructions(input) {
+            self.execute(&mut machine, &instruction)?;
+        }
+        Ok(machine)
+    }
+
+    /// Runs `input`, recording every instruction.
+    pub fn trace<'a>(&self, input: &'a str) -> Result<Trace<'a>, InstructionOverflow> {
+        let start = self.machine();
+        let mut machine = start.clone();
+        let steps = self
+            .instructions(input)
+            .map(|instruction| {
+                let enabled = machine.enabled();
+                self.execute(&mut machine, &instruction)?;
+                Ok(Traced {
+                    instruction,
+                    enabled,
+                    after: machine.clone(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Trace { start, steps })
+    }
 }
 
-#[aoc(day3, part2)]
-pub fn evaluate_do_dont(input: &str) -> u32 {
-    let mut result = 0;
-    let mut chars = input.chars().peekable();
-    while chars.peek().is_some() {
-        skip_if_disabled(&mut chars);
-        let mut current_generator = MultiplicationGenerator::Indicator { index: 0 };
-        for new_char in chars.by_ref() {
-            if current_generator.is_valid(new_char) {
-                if let Some(new_mul) = current_generator.advance(new_char) {
-                    result += new_mul.left as u32 * new_mul.right as u32;
-                    break;
+/// `input` with a line of markers under each of its lines: `^` under
+/// instructions found while enabled, `~` under instructions found while
+/// disabled and `-` under the rest of disabled regions.
+pub fn render_trace(input: &str, trace: &Trace) -> String {
+    let mut markers = String::with_capacity(input.len());
+    let mut steps = trace.steps.iter().peekable();
+    let mut enabled = trace.start.enabled();
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+    for (position, c) in input.char_indices() {
+        while steps
+            .peek()
+            .is_some_and(|step| step.instruction.span.end <= position)
+        {
+            enabled = steps.next().unwrap().after.enabled();
+        }
+        if c == '\n' {
+            lines.push(input[line_start..position].to_string());
+            lines.push(markers.trim_end().to_string());
+            markers.clear();
+            line_start = position + 1;
+            continue;
+        }
+        let marker = match steps.peek() {
+            Some(step) if step.instruction.span.contains(&position) => {
+                if step.enabled {
+                    '^'
+                } else {
+                    '~'
                 }
-            } else {
-                break;
             }
+            _ if !enabled => '-',
+            _ => ' ',
+        };
+        markers.push(marker);
+    }
+    lines.push(input[line_start..].to_string());
+    lines.push(markers.trim_end().to_string());
+    lines.join("\n")
+}
+
+/// The total of running `input`, if it is a valid answer.
+fn interpreted_answer(interpreter: Interpreter, input: &str) -> Result<u64, SolveError> {
+    let total = interpreter.run(input)?.total;
+    u64::try_from(total)
+        .map_err(|_| SolveError::Overflow(format!("the total {total} is not a valid answer")))
+}
+
+#[aoc(day3, part1, Interpreter)]
+pub fn evaluate_interpreted(input: &str) -> Result<u64, SolveError> {
+    interpreted_answer(Interpreter::part1(), input)
+}
+
+#[aoc(day3, part2, Interpreter)]
+pub fn evaluate_do_dont_interpreted(input: &str) -> Result<u64, SolveError> {
+    interpreted_answer(Interpreter::part2(), input)
+}
+
+/// Where `ChunkedEvaluator` is in the memory.
+#[derive(Debug, Clone)]
+enum Scan {
+    Idle,
+    /// the bytes of a toggle seen so far
+    Toggle(Vec<u8>),
+    /// and its bytes seen so far
+    Multiplication(MultiplicationGenerator, Vec<u8>),
+}
+
+/// `evaluate_checked`, fed with chunks of memory as they come in. Matches
+/// may span chunks and only a few bytes of state are kept between them,
+/// along with the open toggle scopes.
+#[derive(Debug, Clone)]
+pub struct ChunkedEvaluator<A: Accumulator = u64> {
+    config: ParserConfig,
+    toggles: ToggleSet,
+    state: ToggleState,
+    scan: Scan,
+    count: usize,
+    result: Result<A, Overflow>,
+}
+
+impl<A: Accumulator> ChunkedEvaluator<A> {
+    pub fn new(config: ParserConfig, toggles: ToggleSet) -> Self {
+        ChunkedEvaluator {
+            config,
+            state: toggles.state(),
+            toggles,
+            scan: Scan::Idle,
+            count: 0,
+            result: Ok(A::default()),
+        }
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) {
+        for &byte in chunk {
+            if self.result.is_err() {
+                return;
+            }
+            self.push(byte);
         }
     }
-    result
+
+    fn push(&mut self, byte: u8) {
+        if self.advance(byte) {
+            return;
+        }
+        // the mismatch may start the next instruction, and so may the bytes
+        // of the broken one after its first
+        let mut replay = match std::mem::replace(&mut self.scan, Scan::Idle) {
+            Scan::Toggle(bytes) => {
+                self.start_multiplication(bytes[0]);
+                bytes[1..].to_vec()
+            }
+            Scan::Multiplication(_, bytes) => bytes[1..].to_vec(),
+            Scan::Idle => Vec::new(),
+        };
+        replay.push(byte);
+        for byte in replay {
+            self.push(byte);
+        }
+    }
+
+    fn start_multiplication(&mut self, byte: u8) {
+        if byte == b'm' {
+            let mut generator = MultiplicationGenerator::Indicator { index: 0 };
+            generator.advance(byte as char);
+            self.scan = Scan::Multiplication(generator, vec![byte]);
+        }
+    }
+
+    /// Settles a toggle cut short by the end of the memory, as the
+    /// multiplications in its bytes still count.
+    fn flush(&mut self) {
+        while let Scan::Toggle(bytes) = &self.scan {
+            let bytes = bytes.clone();
+            self.scan = Scan::Idle;
+            self.start_multiplication(bytes[0]);
+            for &byte in &bytes[1..] {
+                self.push(byte);
+            }
+        }
+    }
+
+    /// Applies the toggle in `bytes` if it is complete.
+    fn complete_toggle(&mut self, bytes: &[u8]) {
+        if let Some(toggle) = self.toggles.toggle_at(bytes) {
+            self.state.apply(toggle.action);
+            self.scan = Scan::Idle;
+        }
+    }
+
+    /// Moves on by `byte`, returning false if it doesn't fit the current
+    /// match.
+    fn advance(&mut self, byte: u8) -> bool {
+        // bytes of multi-byte chars never match
+        let new_char = byte as char;
+        match &mut self.scan {
+            Scan::Idle => {
+                if self.toggles.is_prefix(&[byte]) {
+                    self.scan = Scan::Toggle(vec![byte]);
+                    self.complete_toggle(&[byte]);
+                } else {
+                    self.start_multiplication(byte);
+                }
+            }
+            Scan::Toggle(bytes) => {
+                bytes.push(byte);
+                if !self.toggles.is_prefix(bytes) {
+                    bytes.pop();
+                    return false;
+                }
+                let bytes = bytes.clone();
+                self.complete_toggle(&bytes);
+            }
+            Scan::Multiplication(generator, bytes) => {
+                if !generator.is_valid(&self.config, new_char) {
+                    return false;
+                }
+                bytes.push(byte);
+                if let Some(multiplication) = generator.advance(new_char) {
+                    if self.state.enabled() {
+                        self.result = self
+                            .result
+                            .and_then(|result| accumulate(result, self.count, multiplication));
+                        self.count += 1;
+                    }
+                    self.scan = Scan::Idle;
+                }
+            }
+        }
+        true
+    }
+
+    /// The sum of the multiplications fed so far, as if the memory ended
+    /// here.
+    pub fn result(&self) -> Result<A, Overflow> {
+        let mut evaluator = self.clone();
+        evaluator.flush();
+        evaluator.result
+    }
+}
+
+/// Evaluates the memory read from `reader` in chunks, in constant memory.
+/// An overflow is an `InvalidData` error.
+pub fn evaluate_reader<A: Accumulator>(
+    mut reader: impl Read,
+    config: ParserConfig,
+    toggles: ToggleSet,
+) -> std::io::Result<A> {
+    let mut evaluator = ChunkedEvaluator::new(config, toggles);
+    let mut buffer = vec![0; 1 << 16];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => {
+                return evaluator
+                    .result()
+                    .map_err(|overflow| std::io::Error::new(ErrorKind::InvalidData, overflow))
+            }
+            Ok(read) => evaluator.feed(&buffer[..read]),
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+const CHUNK_SIZE: usize = 4096;
+
+fn evaluate_in_chunks(input: &str, toggles: ToggleSet) -> Result<u64, Overflow> {
+    let mut evaluator = ChunkedEvaluator::new(ParserConfig::PUZZLE, toggles);
+    input
+        .as_bytes()
+        .chunks(CHUNK_SIZE)
+        .for_each(|chunk| evaluator.feed(chunk));
+    evaluator.result()
 }
 
-pub use evaluate_do_dont as part2;
-pub use evaluate_ignore_do_dont as part1;
+#[aoc(day3, part1, Chunked)]
+pub fn evaluate_chunked(input: &str) -> Result<u64, Overflow> {
+    evaluate_in_chunks(input, ToggleSet::none())
+}
+
+#[aoc(day3, part2, Chunked)]
+pub fn evaluate_do_dont_chunked(input: &str) -> Result<u64, Overflow> {
+    evaluate_in_chunks(input, ToggleSet::puzzle())
+}
+
+/// The operand at the start of `memory`, as in `config`, and its length.
+fn operand_at(memory: &[u8], config: &ParserConfig) -> Option<(i128, usize)> {
+    let negative = config.signed && memory.first() == Some(&b'-');
+    let start = negative as usize;
+    let digits = memory[start..]
+        .iter()
+        .take(config.max_digits() + 1)
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+    if digits > config.max_digits() || digits < config.min_digits || (negative && digits == 0) {
+        return None;
+    }
+    let value = memory[start..start + digits]
+        .iter()
+        .fold(0, |value, byte| value * 10 + (byte - b'0') as i128);
+    Some((if negative { -value } else { value }, start + digits))
+}
+
+/// The multiplication at the start of `memory`, and its length.
+fn multiplication_at(memory: &[u8], config: &ParserConfig) -> Option<(Multiplication, usize)> {
+    let mut length = b"mul(".len();
+    if !memory.starts_with(b"mul(") {
+        return None;
+    }
+    let (left, digits) = operand_at(&memory[length..], config)?;
+    length += digits;
+    if memory.get(length) != Some(&b',') {
+        return None;
+    }
+    length += 1;
+    let (right, digits) = operand_at(&memory[length..], config)?;
+    length += digits;
+    if memory.get(length) != Some(&b')') {
+        return None;
+    }
+    Some((Multiplication { left, right }, length + 1))
+}
+
+/// `evaluate_checked` on bytes, jumping from one `m` or start of a toggle
+/// to the next.
+pub fn scan_bytes<A: Accumulator>(
+    memory: &[u8],
+    config: &ParserConfig,
+    toggles: &ToggleSet,
+) -> Result<A, Overflow> {
+    let mut starts_toggle = [false; 256];
+    for toggle in &toggles.toggles {
+        if let Some(&byte) = toggle.token.as_bytes().first() {
+            starts_toggle[byte as usize] = true;
+        }
+    }
+    let mut result = A::default();
+    let mut count = 0;
+    let mut state = toggles.state();
+    let mut position = 0;
+    while let Some(offset) = memory[position..]
+        .iter()
+        .position(|&byte| byte == b'm' || starts_toggle[byte as usize])
+    {
+        let at = position + offset;
+        position = at + 1;
+        if let Some(toggle) = toggles.toggle_at(&memory[at..]) {
+            state.apply(toggle.action);
+            position = at + toggle.token.len();
+        } else if let Some((multiplication, length)) = multiplication_at(&memory[at..], config) {
+            if state.enabled() {
+                result = accumulate(result, count, multiplication)?;
+                count += 1;
+            }
+            position = at + length;
+        }
+    }
+    Ok(result)
+}
+
+#[aoc(day3, part1, Bytes)]
+pub fn evaluate_bytes(input: &str) -> Result<u64, Overflow> {
+    scan_bytes(input.as_bytes(), &ParserConfig::PUZZLE, &ToggleSet::none())
+}
+
+#[aoc(day3, part2, Bytes)]
+pub fn evaluate_do_dont_bytes(input: &str) -> Result<u64, Overflow> {
+    scan_bytes(
+        input.as_bytes(),
+        &ParserConfig::PUZZLE,
+        &ToggleSet::puzzle(),
+    )
+}
+
+pub fn part1(input: &str) -> Result<Answer, SolveError> {
+    evaluate_ignore_do_dont(input).into_answer()
+}
+
+pub fn part2(input: &str) -> Result<Answer, SolveError> {
+    evaluate_do_dont(input).into_answer()
+}
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: u8 = 3;
+    const VARIANTS: &'static [Variant] = &[
+        Variant::new(1, DEFAULT_VARIANT, raw_input, |raw| {
+            evaluate_ignore_do_dont(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(1, "Interpreter", raw_input, |raw| {
+            evaluate_interpreted(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(1, "Chunked", raw_input, |raw| {
+            evaluate_chunked(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(1, "Bytes", raw_input, |raw| {
+            evaluate_bytes(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(2, DEFAULT_VARIANT, raw_input, |raw| {
+            evaluate_do_dont(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(2, "Interpreter", raw_input, |raw| {
+            evaluate_do_dont_interpreted(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(2, "Chunked", raw_input, |raw| {
+            evaluate_do_dont_chunked(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(2, "Bytes", raw_input, |raw| {
+            evaluate_do_dont_bytes(input::<String>(raw)).into_answer()
+        }),
+    ];
+    type Input = String;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        Ok(input.to_owned())
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(evaluate_ignore_do_dont(input)?)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(evaluate_do_dont(input)?)
+    }
+}
 
 #[cfg(test)]
 mod test {
-    use super::{part1, part2};
+    use super::{
+        evaluate_bytes, evaluate_checked, evaluate_chunked, evaluate_do_dont,
+        evaluate_do_dont_bytes, evaluate_do_dont_chunked, evaluate_do_dont_interpreted,
+        evaluate_ignore_do_dont, evaluate_interpreted, evaluate_reader, interpreted_answer, part1,
+        part2, render_trace, scan_bytes, ChunkedEvaluator, Handler, InstructionOverflow,
+        Interpreter, Machine, Multiplication, Overflow, ParserConfig, ToggleAction, ToggleSet,
+    };
+    use crate::error::SolveError;
+    use crate::random::{memory, MemoryOptions, Rng};
     #[test]
     fn test_muls() {
         assert!(
-            part1("xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))") == 161
+            part1("xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))")
+                == Ok(161)
         );
     }
     #[test]
     fn test_do_dont() {
         assert!(
             part2("xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))")
-                == 48
+                == Ok(48)
+        );
+    }
+
+    #[test]
+    fn test_mismatch_starts_instruction() {
+        // the character breaking a match may start the next instruction
+        for (memory, expected) in [
+            ("mmul(2,3)mul(mul(4,5)", [26, 26]),
+            ("ddon't()mul(2,3)ddo()mul(4,5)", [26, 20]),
+            ("mul(2,don't()mul(4,5)", [20, 0]),
+            ("mumul(1,1)mul(2mul(3,3)mul(4,5mul(6,6)", [46, 46]),
+        ] {
+            assert_eq!(
+                [ToggleSet::none(), ToggleSet::puzzle()].map(|toggles| assert_all_agree(
+                    memory,
+                    ParserConfig::PUZZLE,
+                    &toggles
+                )),
+                expected,
+                "{memory}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_dont_after_do() {
+        // a do() ending a disabled stretch may be followed by a don't() right away
+        for (memory, expected) in [
+            ("don't()do()don't()mul(4,5)", 0),
+            ("don't()mul(1,1)do()don't()mul(2,2)do()mul(3,3)", 9),
+            ("do()don't()do()don't()mul(2,2)do()do()mul(3,3)", 9),
+        ] {
+            assert_eq!(
+                assert_all_agree(memory, ParserConfig::PUZZLE, &ToggleSet::puzzle()),
+                expected,
+                "{memory}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_interpreter() {
+        let sample1 = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+        let sample2 = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        assert_eq!(evaluate_interpreted(sample1), Ok(161));
+        assert_eq!(evaluate_do_dont_interpreted(sample2), Ok(48));
+        for memory in [
+            "mmul(2,3)mul(mul(4,5)",
+            "ddon't()mul(2,3)ddo()mul(4,5)",
+            "mul(,3)mul(2,)",
+        ] {
+            assert_all_agree(memory, ParserConfig::PUZZLE, &ToggleSet::none());
+            assert_all_agree(memory, ParserConfig::PUZZLE, &ToggleSet::puzzle());
+        }
+
+        let instructions = Interpreter::part2()
+            .instructions(sample2)
+            .map(|instruction| instruction.text)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            instructions,
+            [
+                "mul(2,4)",
+                "don't()",
+                "mul(5,5)",
+                "mul(11,8)",
+                "do()",
+                "mul(8,5)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_instruction_length() {
+        let config = ParserConfig {
+            min_digits: 1,
+            max_digits: ParserConfig::MAX_DIGITS,
+            signed: true,
+        };
+        let interpreter = Interpreter::part1().with_config(config).with(Handler {
+            name: "nop",
+            arity: 0,
+            run: |_, _| Some(()),
+        });
+        let longest = format!("mul(-{0},-{0})", "9".repeat(38));
+        let texts = |memory: &str| {
+            interpreter
+                .instructions(memory)
+                .map(|instruction| instruction.text.to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(texts(&longest), [longest]);
+        assert_eq!(texts(&format!("mul({},1)nop()", "1".repeat(39))), ["nop()"]);
+        // nothing past the longest instruction is looked at
+        let junk = format!("mul({})nop()", "1,".repeat(100_000));
+        assert_eq!(texts(&junk), ["nop()"]);
+    }
+
+    #[test]
+    fn test_custom_instructions() {
+        let interpreter = Interpreter::part2()
+            .with(Handler {
+                name: "add",
+                arity: 1,
+                run: |machine, args| {
+                    machine.total = machine.total.checked_add(args[0])?;
+                    Some(())
+                },
+            })
+            .with(Handler {
+                name: "sub",
+                arity: 1,
+                run: |machine, args| {
+                    machine.total = machine.total.checked_sub(args[0])?;
+                    Some(())
+                },
+            })
+            .with(Handler {
+                name: "reset",
+                arity: 0,
+                run: |machine, _| {
+                    *machine = Machine::default();
+                    Some(())
+                },
+            });
+        assert_eq!(
+            interpreter
+                .run("mul(2,3)add(4)sub(20)add(1,2)")
+                .unwrap()
+                .total,
+            -10
+        );
+        assert_eq!(
+            interpreter
+                .run("mul(2,3)don't()reset()mul(1,1)")
+                .unwrap()
+                .total,
+            1
+        );
+        // without their handlers, these aren't instructions
+        assert_eq!(
+            Interpreter::part1()
+                .run("add(4)do()mul(1,2)")
+                .unwrap()
+                .total,
+            2
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        let sample = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let trace = Interpreter::part2().trace(sample).unwrap();
+        let steps = trace
+            .steps
+            .iter()
+            .map(|step| {
+                (
+                    step.instruction.span.clone(),
+                    step.instruction.args.clone(),
+                    step.enabled,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            steps,
+            [
+                (1..9, vec![2, 4], true),
+                (20..27, vec![], true),
+                (28..36, vec![5, 5], false),
+                (48..57, vec![11, 8], false),
+                (59..63, vec![], false),
+                (64..72, vec![8, 5], true),
+            ]
+        );
+        assert_eq!(trace.steps.last().unwrap().after.total, 48);
+        assert_eq!(
+            render_trace(sample, &trace),
+            "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))\n \
+             ^^^^^^^^           ^^^^^^^-~~~~~~~~------------~~~~~~~~~--~~~~ ^^^^^^^^"
+        );
+        assert_eq!(
+            render_trace(
+                "don't()\nmul(1,2)",
+                &Interpreter::part2().trace("don't()\nmul(1,2)").unwrap()
+            ),
+            "don't()\n^^^^^^^\nmul(1,2)\n~~~~~~~~"
+        );
+        // disabled from the start, without any instruction
+        let interpreter = Interpreter::part2().with_toggles(ToggleSet::puzzle().starting(false));
+        assert_eq!(
+            render_trace("mul(", &interpreter.trace("mul(").unwrap()),
+            "mul(\n----"
+        );
+        assert_eq!(
+            render_trace("ab\ndo()", &interpreter.trace("ab\ndo()").unwrap()),
+            "ab\n--\ndo()\n~~~~"
+        );
+    }
+
+    #[test]
+    fn test_chunked() {
+        let samples = [
+            "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))",
+            "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))",
+            "mmul(2,3)mul(mul(4,5)ddon't()mul(2,3)ddo()mul(4,5)do()mul(,3)don't(do()",
+        ];
+        let results = samples.map(|sample| {
+            [ToggleSet::none(), ToggleSet::puzzle()]
+                .map(|toggles| assert_all_agree(sample, ParserConfig::PUZZLE, &toggles))
+        });
+        assert_eq!(results, [[161, 161], [161, 48], [52, 46]]);
+    }
+
+    #[test]
+    fn test_parser_config() {
+        let memory = "mul(1234,5)mul(99999,1)mul(12,3)mul(-2,3)mul(,4)mul(3,-)";
+        let results = |config| assert_all_agree(memory, config, &ToggleSet::puzzle());
+        assert_eq!(evaluate_ignore_do_dont(memory), Ok(36));
+        assert_eq!(results(ParserConfig::PUZZLE), 36);
+        let config = ParserConfig {
+            max_digits: 5,
+            ..ParserConfig::PUZZLE
+        };
+        assert_eq!(results(config), 6170 + 99999 + 36);
+        let config = ParserConfig {
+            min_digits: 2,
+            signed: true,
+            ..ParserConfig::PUZZLE
+        };
+        // every mul has a one-digit operand
+        assert_eq!(results(config), 0);
+        let config = ParserConfig {
+            signed: true,
+            ..ParserConfig::PUZZLE
+        };
+        assert_eq!(results(config), 36 - 6);
+        let config = ParserConfig {
+            min_digits: 0,
+            ..ParserConfig::PUZZLE
+        };
+        assert_eq!(results(config), 36);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let memory = "mul(999,999)".repeat(5000);
+        assert_eq!(
+            evaluate_checked::<u64>(&memory, &ParserConfig::PUZZLE, &ToggleSet::none()),
+            Ok(4_990_005_000)
+        );
+        assert_eq!(
+            evaluate_checked::<u32>(&memory, &ParserConfig::PUZZLE, &ToggleSet::none()),
+            Err(Overflow {
+                index: 4303,
+                multiplication: Multiplication {
+                    left: 999,
+                    right: 999
+                }
+            })
+        );
+        assert_eq!(
+            evaluate_checked::<u64>(
+                "mul(-1,1)",
+                &ParserConfig {
+                    signed: true,
+                    ..ParserConfig::PUZZLE
+                },
+                &ToggleSet::none()
+            )
+            .unwrap_err()
+            .to_string(),
+            "multiplication 1 (mul(-1,1)) overflows the result"
+        );
+        // fits a u128 but not an i128
+        let memory = "mul(20000000000000000000,10000000000000000000)";
+        let config = ParserConfig {
+            max_digits: 20,
+            ..ParserConfig::PUZZLE
+        };
+        assert_eq!(
+            evaluate_checked::<u128>(memory, &config, &ToggleSet::none()),
+            Ok(200_000_000_000_000_000_000_000_000_000_000_000_000)
+        );
+        assert!(evaluate_checked::<i128>(memory, &config, &ToggleSet::none()).is_err());
+        let signed = ParserConfig {
+            signed: true,
+            ..config
+        };
+        assert_eq!(
+            evaluate_checked::<i64>("mul(-2,-3)mul(-3,4)mul(0,-5)", &signed, &ToggleSet::none()),
+            Ok(-6)
+        );
+        assert_eq!(
+            evaluate_checked::<u64>("mul(-2,-3)mul(0,-5)", &signed, &ToggleSet::none()),
+            Ok(6)
+        );
+        let config = ParserConfig {
+            max_digits: 38,
+            ..ParserConfig::PUZZLE
+        };
+        let huge = format!("mul({0},{0})", "9".repeat(38));
+        assert!(evaluate_checked::<u128>(&huge, &config, &ToggleSet::none()).is_err());
+        let error =
+            evaluate_reader::<u128>(huge.as_bytes(), config, ToggleSet::none()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        // the interpreter checks its total too
+        let memory = format!("mul(1,1){huge}");
+        assert_eq!(
+            Interpreter::part1().with_config(config).run(&memory),
+            Err(InstructionOverflow {
+                text: huge.clone(),
+                span: 8..8 + huge.len()
+            })
+        );
+        let sub = Handler {
+            name: "sub",
+            arity: 1,
+            run: |machine, args| {
+                machine.total = machine.total.checked_sub(args[0])?;
+                Some(())
+            },
+        };
+        assert_eq!(
+            interpreted_answer(Interpreter::part1().with(sub), "mul(2,3)sub(7)"),
+            Err(SolveError::Overflow(
+                "the total -1 is not a valid answer".to_string()
+            ))
         );
     }
+
+    #[test]
+    fn test_bytes() {
+        let sample1 = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+        let sample2 = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        assert_eq!(evaluate_bytes(sample1), Ok(161));
+        assert_eq!(evaluate_do_dont_bytes(sample2), Ok(48));
+
+        let configs = [
+            ParserConfig::PUZZLE,
+            ParserConfig {
+                min_digits: 0,
+                max_digits: 5,
+                signed: true,
+            },
+        ];
+        let mut rng = Rng::new(3);
+        for _ in 0..20 {
+            let memory =
+                memory(&mut rng, &MemoryOptions::default()) + "mul(-4,5)mul(,2)mmul(12345,2)";
+            for config in &configs {
+                for toggles in [ToggleSet::none(), ToggleSet::puzzle()] {
+                    assert_all_agree(&memory, *config, &toggles);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_toggles() {
+        let toggles = ToggleSet::puzzle()
+            .with("on()", ToggleAction::Enable)
+            .with("off()", ToggleAction::Disable)
+            .with("begin_on()", ToggleAction::Open(true))
+            .with("begin_off()", ToggleAction::Open(false))
+            .with("end()", ToggleAction::Close);
+        let memory = "mul(1,1)off()mul(2,2)on()mul(3,3)begin_off()mul(4,4)begin_on()mul(5,5)\
+                      end()mul(6,6)end()mul(7,7)don't()mul(8,8)do()mul(9,9)end()mul(10,10)\
+                      begin_on()don't()mul(11,11)end()mul(12,12)";
+        let results = |toggles: &ToggleSet| assert_all_agree(memory, ParserConfig::PUZZLE, toggles);
+        assert_eq!(results(&toggles), 1 + 9 + 25 + 49 + 81 + 100 + 144);
+        assert_eq!(
+            results(&toggles.clone().starting(false)),
+            9 + 25 + 49 + 81 + 100 + 144
+        );
+        assert_eq!(
+            results(&ToggleSet::none()),
+            (1..=12).map(|n| n * n).sum::<i128>()
+        );
+
+        // the shortest toggle wins, and a toggle may start with an m
+        let toggles = ToggleSet::none()
+            .with("mute", ToggleAction::Disable)
+            .with("mute!", ToggleAction::Enable)
+            .with("x", ToggleAction::Enable);
+        assert_eq!(
+            assert_all_agree(
+                "mul(2,2)mutmul(3,3)mute!mul(4,4)xmul(5,5)",
+                ParserConfig::PUZZLE,
+                &toggles
+            ),
+            4 + 9 + 25
+        );
+    }
+
+    #[test]
+    fn test_toggles_agree() {
+        // a toggle inside a broken multiplication still counts
+        let toggles = ToggleSet::none().with("l(", ToggleAction::Disable);
+        assert_eq!(
+            assert_all_agree("mul(xmul(2,3)", ParserConfig::PUZZLE, &toggles),
+            0
+        );
+        assert_eq!(
+            assert_all_agree("mul(1,2)mul(2,3)", ParserConfig::PUZZLE, &toggles),
+            8
+        );
+
+        let pieces = [
+            "mul(",
+            "mul(1,2)",
+            "mul(12,3)",
+            "1",
+            "2",
+            ",",
+            ")",
+            "(",
+            "m",
+            "u",
+            "l",
+            "x",
+            "do()",
+            "don't()",
+            "on",
+            "off",
+            "l(",
+            "é",
+        ];
+        let tokens = [
+            "l(",
+            "mu",
+            "x",
+            "(1",
+            "on",
+            "off",
+            "do()",
+            "don't()",
+            "ul(1",
+            "2)m",
+            "mul(1,2)x",
+            "é",
+            "m",
+        ];
+        let actions = [
+            ToggleAction::Enable,
+            ToggleAction::Disable,
+            ToggleAction::Open(true),
+            ToggleAction::Open(false),
+            ToggleAction::Close,
+        ];
+        let mut rng = Rng::new(25);
+        for _ in 0..300 {
+            let mut toggles = ToggleSet::none().starting(rng.chance(0.7));
+            for _ in 0..rng.range(1..=4) {
+                let token = *rng.choose(&tokens);
+                toggles = toggles.with(token, *rng.choose(&actions));
+            }
+            let memory = (0..rng.range(0..=20))
+                .map(|_| *rng.choose(&pieces))
+                .collect::<String>();
+            assert_all_agree(&memory, ParserConfig::PUZZLE, &toggles);
+        }
+    }
+
+    /// Runs every evaluator on `memory`, checks that they agree and returns
+    /// their sum. With the puzzle's parser, also checks the `#[aoc]` solvers.
+    fn assert_all_agree(memory: &str, config: ParserConfig, toggles: &ToggleSet) -> i128 {
+        let checked = evaluate_checked::<i128>(memory, &config, toggles).unwrap();
+        let interpreted = Interpreter::part1()
+            .with_config(config)
+            .with_toggles(toggles.clone())
+            .run(memory)
+            .unwrap()
+            .total;
+        assert_eq!(interpreted, checked, "interpreted {memory:?}");
+        assert_eq!(
+            scan_bytes::<i128>(memory.as_bytes(), &config, toggles),
+            Ok(checked),
+            "bytes {memory:?}"
+        );
+
+        let bytes = memory.as_bytes();
+        let chunked = |chunks: &mut dyn Iterator<Item = &[u8]>| {
+            let mut evaluator = ChunkedEvaluator::<i128>::new(config, toggles.clone());
+            chunks.for_each(|chunk| evaluator.feed(chunk));
+            evaluator.result()
+        };
+        for split in (0..=bytes.len()).step_by((bytes.len() / 100).max(1)) {
+            assert_eq!(
+                chunked(&mut [&bytes[..split], &bytes[split..]].into_iter()),
+                Ok(checked),
+                "{memory:?} split at {split}"
+            );
+        }
+        assert_eq!(chunked(&mut bytes.chunks(1)), Ok(checked), "{memory:?}");
+        assert_eq!(
+            evaluate_reader::<i128>(bytes, config, toggles.clone()).unwrap(),
+            checked
+        );
+
+        if config == ParserConfig::PUZZLE {
+            let expected = u64::try_from(checked).ok();
+            let solvers = if *toggles == ToggleSet::none() {
+                [
+                    evaluate_ignore_do_dont(memory).ok(),
+                    evaluate_interpreted(memory).ok(),
+                    evaluate_chunked(memory).ok(),
+                    evaluate_bytes(memory).ok(),
+                ]
+            } else if *toggles == ToggleSet::puzzle() {
+                [
+                    evaluate_do_dont(memory).ok(),
+                    evaluate_do_dont_interpreted(memory).ok(),
+                    evaluate_do_dont_chunked(memory).ok(),
+                    evaluate_do_dont_bytes(memory).ok(),
+                ]
+            } else {
+                [expected; 4]
+            };
+            assert_eq!(solvers, [expected; 4], "solvers {memory:?}");
+        }
+        checked
+    }
 }
@@ -1,16 +1,58 @@
-use ndarray::Array2;
+use crate::error::{ParseError, SolveError};
+use crate::grid::{Grid, DIAGONALS, NEIGHBOURS_8};
+use crate::solution::{
+    input, parse_input, raw_input, Answer, IntoAnswer, Solution, Variant, DEFAULT_VARIANT,
+};
 
 #[aoc(day4, part1, Direct)]
-pub fn count_xmas_direct(input: &str) -> u32 {
-    count_xmas(&generate_data(input))
+pub fn count_xmas_direct(input: &str) -> Result<u32, ParseError> {
+    Ok(count_xmas(&try_generate_data(input)?))
 }
 #[aoc(day4, part2, Direct)]
-pub fn count_cross_mas_direct(input: &str) -> u32 {
-    count_cross_mas(&generate_data(input))
+pub fn count_cross_mas_direct(input: &str) -> Result<u32, ParseError> {
+    Ok(count_cross_mas(&try_generate_data(input)?))
 }
 
-pub use count_cross_mas_direct as part2;
-pub use count_xmas_direct as part1;
+pub fn part1(input: &str) -> Result<Answer, SolveError> {
+    count_xmas_direct(input).into_answer()
+}
+
+pub fn part2(input: &str) -> Result<Answer, SolveError> {
+    count_cross_mas_direct(input).into_answer()
+}
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    const DAY: u8 = 4;
+    const VARIANTS: &'static [Variant] = &[
+        Variant::new(1, "Direct", raw_input, |raw| {
+            count_xmas_direct(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(1, DEFAULT_VARIANT, parse_input::<Self>, |parsed| {
+            count_xmas(input(parsed)).into_answer()
+        }),
+        Variant::new(2, "Direct", raw_input, |raw| {
+            count_cross_mas_direct(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(2, DEFAULT_VARIANT, parse_input::<Self>, |parsed| {
+            count_cross_mas(input(parsed)).into_answer()
+        }),
+    ];
+    type Input = Grid<XMasChar>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        try_generate_data(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(count_xmas(input) as Answer)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(count_cross_mas(input) as Answer)
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum XMasChar {
@@ -21,140 +63,71 @@ pub enum XMasChar {
 }
 
 #[aoc_generator(day4)]
-fn generate_data(input: &str) -> Array2<XMasChar> {
-    let column_count = input.find('\n').unwrap();
-    let flattened = input
-        .lines()
-        .flat_map(|line| {
-            line.chars().map(|char| match char {
-                'X' => XMasChar::X,
-                'M' => XMasChar::M,
-                'A' => XMasChar::A,
-                'S' => XMasChar::S,
-                _ => panic!("unexpected character"),
-            })
-        })
-        .collect::<Vec<_>>();
-
-    let line_count = flattened.len() / column_count;
-    Array2::from_shape_vec((line_count, column_count), flattened).unwrap()
+fn generate_data(input: &str) -> Grid<XMasChar> {
+    try_generate_data(input).unwrap_or_else(|error| panic!("{error}"))
 }
 
-static DIRECTION_UP: u8 = 1 << 0;
-static DIRECTION_RIGHT: u8 = 1 << 1;
-static DIRECTION_DOWN: u8 = 1 << 2;
-static DIRECTION_LEFT: u8 = 1 << 3;
-
-fn get_xmas_directions(index: &(usize, usize), dim: &(usize, usize)) -> Vec<(isize, isize)> {
-    let mut directions_allowed = 0;
-    if index.0 >= 3 {
-        directions_allowed |= DIRECTION_UP
-    }
-    if index.0 < dim.0 - 3 {
-        directions_allowed |= DIRECTION_DOWN
-    }
-    if index.1 >= 3 {
-        directions_allowed |= DIRECTION_LEFT
-    }
-    if index.1 < dim.1 - 3 {
-        directions_allowed |= DIRECTION_RIGHT
-    }
-
-    let mut directions = Vec::new();
-    if directions_allowed & DIRECTION_UP != 0 && directions_allowed & DIRECTION_LEFT != 0 {
-        directions.push((-1, -1));
-    }
-    if directions_allowed & DIRECTION_UP != 0 && directions_allowed & DIRECTION_RIGHT != 0 {
-        directions.push((-1, 1));
-    }
-    if directions_allowed & DIRECTION_DOWN != 0 && directions_allowed & DIRECTION_LEFT != 0 {
-        directions.push((1, -1));
-    }
-    if directions_allowed & DIRECTION_DOWN != 0 && directions_allowed & DIRECTION_RIGHT != 0 {
-        directions.push((1, 1));
-    }
-
-    if directions_allowed & DIRECTION_UP != 0 {
-        directions.push((-1, 0))
-    }
-    if directions_allowed & DIRECTION_LEFT != 0 {
-        directions.push((0, -1))
-    }
-    if directions_allowed & DIRECTION_RIGHT != 0 {
-        directions.push((0, 1))
-    }
-    if directions_allowed & DIRECTION_DOWN != 0 {
-        directions.push((1, 0))
-    }
-    directions
+pub fn try_generate_data(input: &str) -> Result<Grid<XMasChar>, ParseError> {
+    Grid::try_parse(4, input, |char| match char {
+        'X' => Some(XMasChar::X),
+        'M' => Some(XMasChar::M),
+        'A' => Some(XMasChar::A),
+        'S' => Some(XMasChar::S),
+        _ => None,
+    })
 }
 
 static XMAS_ORDER: [XMasChar; 4] = [XMasChar::X, XMasChar::M, XMasChar::A, XMasChar::S];
 
 #[aoc(day4, part1)]
-pub fn count_xmas(input: &Array2<XMasChar>) -> u32 {
-    let dim = input.dim();
+pub fn count_xmas(input: &Grid<XMasChar>) -> u32 {
     input
-        .indexed_iter()
-        .filter(|(_, value)| **value == XMasChar::X)
-        .map(|(index, _)| {
-            let match_count = get_xmas_directions(&index, &dim)
+        .positions(|value| *value == XMasChar::X)
+        .map(|index| {
+            NEIGHBOURS_8
                 .iter()
                 .filter(|direction| {
-                    (1..=3).all(|fact| {
-                        input[(
-                            (index.0 as isize + direction.0 * fact as isize) as usize,
-                            (index.1 as isize + direction.1 * fact as isize) as usize,
-                        )] == XMAS_ORDER[fact]
-                    })
+                    input
+                        .ray(index, **direction)
+                        .take(3)
+                        .map(|position| input[position])
+                        .eq(XMAS_ORDER[1..].iter().copied())
                 })
-                .count() as u32;
-            match_count
+                .count() as u32
         })
         .sum()
 }
 
-fn get_cross_mas_directions(index: &(usize, usize), dim: &(usize, usize)) -> Vec<(isize, isize)> {
-    let all_directions_allowed =
-        index.0 >= 1 && index.0 < dim.0 - 1 && index.1 >= 1 && index.1 < dim.1 - 1;
-
-    if all_directions_allowed {
-        vec![(-1, -1), (-1, 1), (1, -1), (1, 1)]
-    } else {
-        vec![]
-    }
-}
-
 #[aoc(day4, part2)]
-pub fn count_cross_mas(input: &Array2<XMasChar>) -> u32 {
-    let dim = input.dim();
+pub fn count_cross_mas(input: &Grid<XMasChar>) -> u32 {
     input
-        .indexed_iter()
-        .filter(|(_, value)| **value == XMasChar::A)
-        .filter(|(index, _)| {
-            get_cross_mas_directions(index, &dim)
+        .positions(|value| *value == XMasChar::A)
+        .filter(|index| {
+            let Some(corners) = DIAGONALS
                 .iter()
-                .fold((0, 0), |mut acc, direction| {
-                    match input[(
-                        (index.0 as isize + direction.0) as usize,
-                        (index.1 as isize + direction.1) as usize,
-                    )] {
-                        XMasChar::X => {}
-                        XMasChar::M => acc.0 += 1,
-                        XMasChar::A => {}
-                        XMasChar::S => acc.1 += 1,
-                    }
-                    acc
-                })
-                == (2, 2)
-                && input[(index.0 + 1, index.1 + 1)] != input[(index.0 - 1, index.1 - 1)]
+                .map(|direction| input.offset(*index, *direction).map(|corner| input[corner]))
+                .collect::<Option<Vec<_>>>()
+            else {
+                return false;
+            };
+            // corners are ordered top left, top right, bottom left, bottom right
+            corners.iter().fold((0, 0), |mut acc, corner| {
+                match corner {
+                    XMasChar::X => {}
+                    XMasChar::M => acc.0 += 1,
+                    XMasChar::A => {}
+                    XMasChar::S => acc.1 += 1,
+                }
+                acc
+            }) == (2, 2)
+                && corners[0] != corners[3]
         })
         .count() as u32
 }
 
 #[cfg(test)]
 mod test {
-    use super::{count_cross_mas, count_xmas, generate_data};
+    use super::{count_cross_mas, count_xmas, generate_data, try_generate_data};
     #[test]
     fn generator() {
         println!(
@@ -165,6 +138,11 @@ mod test {
         );
     }
     #[test]
+    fn test_try_generate_data() {
+        let error = try_generate_data("XMAS\nXMAZ").unwrap_err();
+        assert_eq!((error.day, error.line, error.column), (4, 2, 4));
+    }
+    #[test]
     fn part1() {
         assert_eq!(
             count_xmas(&generate_data("MMMSXXMASM\nMSAMXMSMSA\nAMXSXMAAMM\nMSAMASMSMX\nXMASAMXAMM\nXXAMMXXAMA\nSMSMSASXSS\nSAXAMASAAA\nMAMMMXMMMM\nMXMXAXMASX")),
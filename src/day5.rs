@@ -1,22 +1,69 @@
+use crate::error::{parse_number, ParseError, SolveError};
+use crate::input::normalise;
+use crate::solution::{input, parse_input, raw_input, Answer, IntoAnswer, Solution, Variant};
 use std::cmp::Ordering;
 
 #[aoc(day5, part1, Direct)]
-pub fn sum_middle_pages_direct(input: &str) -> u32 {
-    sum_middle_pages_fast(&generator(input))
+pub fn sum_middle_pages_direct(input: &str) -> Result<u32, ParseError> {
+    Ok(sum_middle_pages_fast(&try_generator(input)?))
 }
 #[aoc(day5, part2, Direct)]
-pub fn sum_middle_pages_ordered_direct(input: &str) -> u32 {
-    sum_middle_pages_ordered_fast(&generator(input))
+pub fn sum_middle_pages_ordered_direct(input: &str) -> Result<u32, ParseError> {
+    Ok(sum_middle_pages_ordered_fast(&try_generator(input)?))
 }
 
-pub use sum_middle_pages_direct as part1;
-pub use sum_middle_pages_ordered_direct as part2;
+pub fn part1(input: &str) -> Result<Answer, SolveError> {
+    sum_middle_pages_direct(input).into_answer()
+}
+
+pub fn part2(input: &str) -> Result<Answer, SolveError> {
+    sum_middle_pages_ordered_direct(input).into_answer()
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    const DAY: u8 = 5;
+    const VARIANTS: &'static [Variant] = &[
+        Variant::new(1, "Direct", raw_input, |raw| {
+            sum_middle_pages_direct(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(1, "CheckRules", parse_input::<Self>, |parsed| {
+            sum_middle_pages(input(parsed)).into_answer()
+        }),
+        Variant::new(1, "CheckSorted", parse_input::<Self>, |parsed| {
+            sum_middle_pages_fast(input(parsed)).into_answer()
+        }),
+        Variant::new(2, "Direct", raw_input, |raw| {
+            sum_middle_pages_ordered_direct(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(2, "FilterByChecking", parse_input::<Self>, |parsed| {
+            sum_middle_pages_ordered(input(parsed)).into_answer()
+        }),
+        Variant::new(2, "FilterByUnSorted", parse_input::<Self>, |parsed| {
+            sum_middle_pages_ordered_fast(input(parsed)).into_answer()
+        }),
+    ];
+    type Input = (Vec<Rule>, Vec<Update>);
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        try_generator(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(sum_middle_pages_fast(input) as Answer)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(sum_middle_pages_ordered_fast(input) as Answer)
+    }
+}
 
 type PageId = u8;
 type Update = Vec<PageId>;
 
 #[derive(Debug)]
-struct Rule {
+pub struct Rule {
     leading: PageId,
     trailing: PageId,
 }
@@ -41,6 +88,7 @@ fn compare(a: &PageId, b: &PageId, rules: &[Rule]) -> Ordering {
 }
 
 impl Rule {
+    #[allow(clippy::manual_contains)]
     fn satisfied(&self, update: &Update) -> bool {
         let failed = update
             .iter()
@@ -57,30 +105,32 @@ impl Rule {
 
 #[aoc_generator(day5)]
 fn generator(input: &str) -> (Vec<Rule>, Vec<Update>) {
-    let (_rules_done, rules, updates) = input.lines().fold(
-        (false, Vec::new(), Vec::new()),
-        |(rules_done, mut rules, mut updates), line| {
-            if line.is_empty() {
-                return (true, rules, updates);
-            }
-            if rules_done {
-                updates.push(
-                    line.split(',')
-                        .map(|page| page.parse::<PageId>().unwrap())
-                        .collect::<Update>(),
-                )
-            } else {
-                let (leading, trailing) = line.split_once('|').unwrap();
+    try_generator(input).unwrap_or_else(|error| panic!("{error}"))
+}
 
-                rules.push(Rule {
-                    leading: leading.parse::<PageId>().unwrap(),
-                    trailing: trailing.parse::<PageId>().unwrap(),
-                });
-            }
-            (rules_done, rules, updates)
-        },
-    );
-    (rules, updates)
+pub fn try_generator(input: &str) -> Result<(Vec<Rule>, Vec<Update>), ParseError> {
+    let input = normalise(input);
+    let mut rules_done = false;
+    let mut rules = Vec::new();
+    let mut updates = Vec::new();
+    for (line_index, line) in input.lines().enumerate() {
+        let parse = |page: &str| parse_number::<PageId>(5, line_index, line, page);
+        if line.is_empty() {
+            rules_done = true;
+        } else if rules_done {
+            updates.push(line.split(',').map(parse).collect::<Result<Update, _>>()?)
+        } else {
+            let (leading, trailing) = line.split_once('|').ok_or_else(|| {
+                ParseError::in_line(5, line_index, line, line, "expected a rule like 47|53")
+            })?;
+
+            rules.push(Rule {
+                leading: parse(leading)?,
+                trailing: parse(trailing)?,
+            });
+        }
+    }
+    Ok((rules, updates))
 }
 
 fn check_rules_for_update(rules: &[Rule], update: &Update) -> bool {
@@ -107,8 +157,8 @@ fn sum_middle_pages_fast(input: &(Vec<Rule>, Vec<Update>)) -> u32 {
         .sum()
 }
 
-#[allow(clippy::manual_inspect)]
 #[aoc(day5, part2, FilterByChecking)]
+#[allow(clippy::manual_inspect)]
 fn sum_middle_pages_ordered(input: &(Vec<Rule>, Vec<Update>)) -> u32 {
     let (rules, updates) = input;
 
@@ -145,12 +195,21 @@ fn sum_middle_pages_ordered_fast(input: &(Vec<Rule>, Vec<Update>)) -> u32 {
 
 #[cfg(test)]
 mod test {
-    use super::{generator, sum_middle_pages, sum_middle_pages_ordered};
+    use super::{generator, sum_middle_pages, sum_middle_pages_ordered, try_generator};
     #[test]
     fn test_generator() {
         generator("47|53\n97|13\n97|61\n97|47\n75|29\n61|13\n75|53\n29|13\n97|29\n53|29\n61|53\n97|53\n61|29\n47|13\n75|47\n97|75\n47|61\n75|61\n47|29\n75|13\n53|13\n\n75,47,61,53,29\n97,61,53,29,13\n75,29,13\n75,97,47,61,53\n61,13,29\n97,13,75,29,47");
     }
     #[test]
+    fn test_try_generator() {
+        let error = try_generator("47|53\n97-13\n\n75,47").unwrap_err();
+        assert_eq!((error.day, error.line, error.column), (5, 2, 1));
+        let error = try_generator("47|53\n\n75,47,,61").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 7));
+        let (rules, updates) = try_generator("47|53\r\n\r\n75,47\r\n").unwrap();
+        assert_eq!((rules.len(), updates), (1, vec![vec![75, 47]]));
+    }
+    #[test]
     fn test_count_middle_pages() {
         let gen = generator("47|53\n97|13\n97|61\n97|47\n75|29\n61|13\n75|53\n29|13\n97|29\n53|29\n61|53\n97|53\n61|29\n47|13\n75|47\n97|75\n47|61\n75|61\n47|29\n75|13\n53|13\n\n75,47,61,53,29\n97,61,53,29,13\n75,29,13\n75,97,47,61,53\n61,13,29\n97,13,75,29,47");
         assert_eq!(sum_middle_pages(&gen), 143);
@@ -1,10 +1,14 @@
-use ndarray::Array2;
+use crate::error::{ParseError, SolveError};
+use crate::grid::{Grid, Position};
+use crate::input::normalise;
+use crate::solution::{
+    input, parse_input, raw_input, Answer, IntoAnswer, Solution, Variant, DEFAULT_VARIANT,
+};
 #[cfg(test)]
 use std::fmt::Display;
-use std::sync::{Arc, RwLock};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum Direction {
+pub enum Direction {
     North,
     East,
     South,
@@ -30,7 +34,7 @@ impl Direction {
 }
 
 #[derive(Debug, Copy, Clone)]
-enum GuardMapItem {
+pub enum GuardMapItem {
     Empty,
     Guard {
         direction: Direction,
@@ -64,23 +68,23 @@ impl Display for GuardMapItem {
     }
 }
 
-type GuardMap = Array2<GuardMapItem>;
+pub type GuardMap = Grid<GuardMapItem>;
 
 #[aoc(day6, part1, Direct)]
-pub fn count_distinct_fields_direct(input: &str) -> u32 {
-    let (mut map, pos) = generate_map(input);
+pub fn count_distinct_fields_direct(input: &str) -> Result<u32, ParseError> {
+    let (mut map, pos) = try_generate_map(input)?;
     match walk_map_recursive(&mut map, pos) {
-        MapType::Exitable(result) => result,
+        MapType::Exitable(result) => Ok(result),
         _ => panic!("given map for part 1 is a loop"),
     }
 }
 
 #[aoc(day6, part2, Direct)]
-pub fn count_obstruction_options_direct(input: &str) -> u32 {
-    let (initial_guard_map, guard_position) = generate_map(input);
+pub fn count_obstruction_options_direct(input: &str) -> Result<u32, ParseError> {
+    let (initial_guard_map, guard_position) = try_generate_map(input)?;
     let mut guard_map = initial_guard_map.clone();
     walk_map_recursive(&mut guard_map, guard_position);
-    guard_map
+    let count = guard_map
         .indexed_iter()
         .filter_map(|(index, field)| {
             if matches!(field, GuardMapItem::Covered) && index != guard_position {
@@ -99,64 +103,92 @@ pub fn count_obstruction_options_direct(input: &str) -> u32 {
                 MapType::Loop
             )
         })
-        .count() as u32
+        .count();
+    Ok(count as u32)
 }
 
-pub use count_distinct_fields_direct as part1;
-pub use count_obstruction_options_direct as part2;
+pub fn part1(input: &str) -> Result<Answer, SolveError> {
+    count_distinct_fields_direct(input).into_answer()
+}
 
-#[aoc_generator(day6)]
-fn generate_map(input: &str) -> (GuardMap, (usize, usize)) {
-    let guard_position = Arc::new(RwLock::new((0, 0)));
-    let dimension = Arc::new(RwLock::new((0, 0)));
+pub fn part2(input: &str) -> Result<Answer, SolveError> {
+    count_obstruction_options_direct(input).into_answer()
+}
 
-    let lines = input.lines().enumerate().flat_map(|(line_no, line)| {
-        let guard_position = &guard_position;
-        if line_no == 0 {
-            dimension
-                .write()
-                .map(|mut dim| {
-                    *dim = (line.len(), line.len());
-                })
-                .unwrap()
-        }
-        line.chars().enumerate().map(move |(char_no, char)| {
-            let item = match char {
-                '.' => GuardMapItem::Empty,
-                '#' => GuardMapItem::Obstruction {
-                    visited_direction: None,
-                },
-                '^' => GuardMapItem::Guard {
-                    direction: Direction::North,
-                },
-                '>' => GuardMapItem::Guard {
-                    direction: Direction::East,
-                },
-                '<' => GuardMapItem::Guard {
-                    direction: Direction::West,
-                },
-                'v' => GuardMapItem::Guard {
-                    direction: Direction::South,
-                },
-                _ => panic!(),
-            };
+pub struct Day6;
 
-            if matches!(item, GuardMapItem::Guard { direction: _ }) {
-                guard_position
-                    .write()
-                    .map(|mut position| {
-                        *position = (line_no, char_no);
-                    })
-                    .unwrap()
-            }
-            item
-        })
-    });
+impl Solution for Day6 {
+    const DAY: u8 = 6;
+    const VARIANTS: &'static [Variant] = &[
+        Variant::new(1, "Direct", raw_input, |raw| {
+            count_distinct_fields_direct(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(1, DEFAULT_VARIANT, parse_input::<Self>, |parsed| {
+            count_distinct_fields(input(parsed)).into_answer()
+        }),
+        Variant::new(2, "Direct", raw_input, |raw| {
+            count_obstruction_options_direct(input::<String>(raw)).into_answer()
+        }),
+        Variant::new(2, DEFAULT_VARIANT, parse_input::<Self>, |parsed| {
+            count_obstruction_options(input(parsed)).into_answer()
+        }),
+    ];
+    type Input = (GuardMap, Position);
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        try_generate_map(input)
+    }
 
-    let collected = lines.collect();
-    let map = Array2::from_shape_vec(*dimension.read().unwrap(), collected).unwrap();
-    let pos = *guard_position.read().unwrap();
-    (map, pos)
+    fn part1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(count_distinct_fields(input) as Answer)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(count_obstruction_options(input) as Answer)
+    }
+}
+
+#[aoc_generator(day6)]
+fn generate_map(input: &str) -> (GuardMap, Position) {
+    try_generate_map(input).unwrap_or_else(|error| panic!("{error}"))
+}
+
+pub fn try_generate_map(input: &str) -> Result<(GuardMap, Position), ParseError> {
+    let input = normalise(input);
+    let map = Grid::try_parse(6, &input, |char| match char {
+        '.' => Some(GuardMapItem::Empty),
+        '#' => Some(GuardMapItem::Obstruction {
+            visited_direction: None,
+        }),
+        '^' => Some(GuardMapItem::Guard {
+            direction: Direction::North,
+        }),
+        '>' => Some(GuardMapItem::Guard {
+            direction: Direction::East,
+        }),
+        '<' => Some(GuardMapItem::Guard {
+            direction: Direction::West,
+        }),
+        'v' => Some(GuardMapItem::Guard {
+            direction: Direction::South,
+        }),
+        _ => None,
+    })?;
+    let guards = map
+        .positions(|item| matches!(item, GuardMapItem::Guard { direction: _ }))
+        .take(2)
+        .collect::<Vec<_>>();
+    match (guards.first().copied(), guards.get(1).copied()) {
+        (Some(pos), None) => Ok((map, pos)),
+        (None, _) => Err(ParseError::new(6, 1, 1, "", "the map contains no guard")),
+        (Some(_), Some((line, column))) => Err(ParseError::new(
+            6,
+            line + 1,
+            column + 1,
+            input.lines().nth(line).unwrap_or_default(),
+            "the map contains more than one guard",
+        )),
+    }
 }
 
 enum MapType {
@@ -164,18 +196,16 @@ enum MapType {
     Exitable(u32),
 }
 
-fn walk_map_recursive(guard_map: &mut GuardMap, mut guard_position: (usize, usize)) -> MapType {
+fn walk_map_recursive(guard_map: &mut GuardMap, mut guard_position: Position) -> MapType {
     let direction = match guard_map[guard_position] {
         GuardMapItem::Guard { direction } => direction,
         _ => unreachable!(),
     };
 
-    let direction_vector = direction.get_vector();
-    let new_position = (
-        (guard_position.0 as isize + direction_vector.0) as usize,
-        (guard_position.1 as isize + direction_vector.1) as usize,
-    );
-    if let Some(item) = guard_map.get_mut(new_position) {
+    if let Some((new_position, item)) = guard_map
+        .offset(guard_position, direction.get_vector())
+        .and_then(|new_position| Some((new_position, guard_map.get_mut(new_position)?)))
+    {
         let newly_covered = match item {
             GuardMapItem::Covered => {
                 guard_map[new_position] = GuardMapItem::Guard { direction };
@@ -214,7 +244,7 @@ fn walk_map_recursive(guard_map: &mut GuardMap, mut guard_position: (usize, usiz
 }
 
 #[aoc(day6, part1)]
-fn count_distinct_fields((guard_map, guard_position): &(GuardMap, (usize, usize))) -> u32 {
+fn count_distinct_fields((guard_map, guard_position): &(GuardMap, Position)) -> u32 {
     let mut guard_map = guard_map.clone();
     match walk_map_recursive(&mut guard_map, *guard_position) {
         MapType::Exitable(result) => result,
@@ -223,7 +253,7 @@ fn count_distinct_fields((guard_map, guard_position): &(GuardMap, (usize, usize)
 }
 
 #[aoc(day6, part2)]
-fn count_obstruction_options((guard_map, guard_position): &(GuardMap, (usize, usize))) -> u32 {
+fn count_obstruction_options((guard_map, guard_position): &(GuardMap, Position)) -> u32 {
     let mut guard_map = guard_map.clone();
     let initial_guard_map = guard_map.clone();
     walk_map_recursive(&mut guard_map, *guard_position);
@@ -253,7 +283,17 @@ fn count_obstruction_options((guard_map, guard_position): &(GuardMap, (usize, us
 mod test {
     use crate::day6::count_obstruction_options;
 
-    use super::{count_distinct_fields, generate_map, walk_map_recursive};
+    use super::{count_distinct_fields, generate_map, try_generate_map, walk_map_recursive};
+
+    #[test]
+    fn test_try_generate_map() {
+        let error = try_generate_map("..#\n.^.\n..@").unwrap_err();
+        assert_eq!((error.day, error.line, error.column), (6, 3, 3));
+        let error = try_generate_map("..#\n...").unwrap_err();
+        assert_eq!(error.reason, "the map contains no guard");
+        let error = try_generate_map("..#\n.^.\n..<").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 3));
+    }
 
     #[test]
     fn test_walk() {
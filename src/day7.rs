@@ -1,8 +1,11 @@
+use crate::error::{parse_number, ParseError, SolveError};
+use crate::input::normalise;
+use crate::solution::{input, parse_input, Answer, IntoAnswer, Solution, Variant, DEFAULT_VARIANT};
 use rayon::prelude::*;
 use std::collections::VecDeque;
 
 #[derive(Debug, Clone)]
-struct Equation {
+pub struct Equation {
     solution: f64,
     parts: VecDeque<usize>,
 }
@@ -54,18 +57,24 @@ impl Equation {
 
 #[aoc_generator(day7)]
 fn generate_equations(input: &str) -> Vec<Equation> {
-    input
+    try_generate_equations(input).unwrap_or_else(|error| panic!("{error}"))
+}
+
+pub fn try_generate_equations(input: &str) -> Result<Vec<Equation>, ParseError> {
+    normalise(input)
         .lines()
-        .map(|line| {
-            let (solution, parts) = line.split_once(':').unwrap();
-            Equation {
-                solution: solution.parse().unwrap(),
+        .enumerate()
+        .map(|(line_index, line)| {
+            let (solution, parts) = line.split_once(':').ok_or_else(|| {
+                ParseError::in_line(7, line_index, line, line, "expected a colon")
+            })?;
+            Ok(Equation {
+                solution: parse_number(7, line_index, line, solution)?,
                 parts: parts
-                    .trim()
-                    .split(' ')
-                    .map(|part| part.parse::<usize>().unwrap())
-                    .collect(),
-            }
+                    .split_whitespace()
+                    .map(|part| parse_number::<usize>(7, line_index, line, part))
+                    .collect::<Result<_, _>>()?,
+            })
         })
         .collect()
 }
@@ -98,17 +107,45 @@ fn sum_solveable_equations_concat(equations: &[Equation]) -> usize {
         .sum::<f64>() as usize
 }
 
-pub fn part1(input: &str) -> usize {
-    sum_solveable_equations(&generate_equations(input))
+pub fn part1(input: &str) -> Result<Answer, SolveError> {
+    sum_solveable_equations(&try_generate_equations(input)?).into_answer()
+}
+
+pub fn part2(input: &str) -> Result<Answer, SolveError> {
+    sum_solveable_equations_concat(&try_generate_equations(input)?).into_answer()
 }
-pub fn part2(input: &str) -> usize {
-    sum_solveable_equations_concat(&generate_equations(input))
+
+pub struct Day7;
+
+impl Solution for Day7 {
+    const DAY: u8 = 7;
+    const VARIANTS: &'static [Variant] = &[
+        Variant::new(1, DEFAULT_VARIANT, parse_input::<Self>, |parsed| {
+            sum_solveable_equations(input::<Vec<Equation>>(parsed)).into_answer()
+        }),
+        Variant::new(2, DEFAULT_VARIANT, parse_input::<Self>, |parsed| {
+            sum_solveable_equations_concat(input::<Vec<Equation>>(parsed)).into_answer()
+        }),
+    ];
+    type Input = Vec<Equation>;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        try_generate_equations(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(sum_solveable_equations(input) as Answer)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(sum_solveable_equations_concat(input) as Answer)
+    }
 }
 #[cfg(test)]
 mod test {
     use crate::day7::sum_solveable_equations_concat;
 
-    use super::{generate_equations, sum_solveable_equations};
+    use super::{generate_equations, sum_solveable_equations, try_generate_equations};
     const INPUT: &str = r#"190: 10 19
 3267: 81 40 27
 83: 17 5
@@ -125,6 +162,15 @@ mod test {
         println!("{:?}", set);
     }
 
+    #[test]
+    fn test_try_generator() {
+        let error = try_generate_equations("190: 10 19\n3267 81 40 27").unwrap_err();
+        assert_eq!((error.day, error.line, error.column), (7, 2, 1));
+        let error = try_generate_equations("190: 10 1x9").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 9));
+        assert_eq!(try_generate_equations("190: 10  19\r\n").unwrap().len(), 1);
+    }
+
     #[test]
     fn test_pt1() {
         let set = generate_equations(INPUT);
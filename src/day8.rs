@@ -1,53 +1,42 @@
+use crate::error::{ParseError, SolveError};
+use crate::grid::{Grid, Position};
+use crate::solution::{input, parse_input, Answer, IntoAnswer, Solution, Variant, DEFAULT_VARIANT};
 use std::collections::HashSet;
-use std::ops::Range;
 
-type Position = (usize, usize);
 type Map = Vec<Position>;
-type Dim = usize;
-fn get_position(index: usize, dim: usize) -> Position {
-    let x = index % dim;
-    let y = (index - x) / dim;
-    (y, x)
-}
+type Antennas = Grid<Option<char>>;
 
 #[aoc_generator(day8)]
-fn generate_maps(input: &str) -> (Vec<Map>, Dim) {
-    let dim = input.find('\n').unwrap();
-    let map = input
-        .lines()
-        .flat_map(|line| {
-            line.chars().map(|ch| match ch {
-                '.' => None,
-                _ => Some(ch as u32),
-            })
-        })
-        .collect::<Vec<Option<u32>>>();
+fn generate_maps(input: &str) -> (Vec<Map>, Antennas) {
+    try_generate_maps(input).unwrap_or_else(|error| panic!("{error}"))
+}
 
-    (
-        map.iter()
+pub fn try_generate_maps(input: &str) -> Result<(Vec<Map>, Antennas), ParseError> {
+    let antennas = Grid::try_parse(8, input, |ch| match ch {
+        '.' => Some(None),
+        _ if ch.is_ascii_alphanumeric() => Some(Some(ch)),
+        _ => None,
+    })?;
+
+    Ok((
+        antennas
+            .iter()
             .filter_map(|item| *item)
-            .collect::<HashSet<u32>>()
+            .collect::<HashSet<char>>()
             .iter()
-            .map(|ascii_index| {
-                map.iter()
-                    .enumerate()
-                    .filter_map(|(index, field)| {
-                        if field.is_some_and(|field_value| field_value == *ascii_index) {
-                            Some(get_position(index, dim))
-                        } else {
-                            None
-                        }
-                    })
+            .map(|frequency| {
+                antennas
+                    .positions(|field| *field == Some(*frequency))
                     .collect::<Vec<Position>>()
             })
             .collect(),
-        dim,
-    )
+        antennas,
+    ))
 }
 
 fn calculate_antinodes(
     (pos_1, pos_2): &(Position, Position),
-    bounds: &Range<isize>,
+    antennas: &Antennas,
     depth: usize,
     include_towers: bool,
 ) -> Vec<Position> {
@@ -57,27 +46,24 @@ fn calculate_antinodes(
     );
     let mut antinodes = Vec::new();
     for multiplier in (if include_towers { 0 } else { 1 })..=depth as isize {
-        let node = (
-            pos_2.0 as isize + delta.0 * multiplier,
-            pos_2.1 as isize + delta.1 * multiplier,
-        );
-        if bounds.contains(&node.0) && bounds.contains(&node.1) {
-            antinodes.push((node.0 as usize, node.1 as usize))
+        let offset = (delta.0 * multiplier, delta.1 * multiplier);
+        if let Some(node) = antennas.offset(*pos_2, offset) {
+            antinodes.push(node)
         }
-        let node = (
-            pos_1.0 as isize - delta.0 * multiplier,
-            pos_1.1 as isize - delta.1 * multiplier,
-        );
-        if bounds.contains(&node.0) && bounds.contains(&node.1) {
-            antinodes.push((node.0 as usize, node.1 as usize))
+        if let Some(node) = antennas.offset(*pos_1, (-offset.0, -offset.1)) {
+            antinodes.push(node)
         }
     }
     antinodes
 }
 
-fn get_antinode_locs(map: &Map, dim: &usize, depth: usize, include_towers: bool) -> Vec<Position> {
+fn get_antinode_locs(
+    map: &Map,
+    antennas: &Antennas,
+    depth: usize,
+    include_towers: bool,
+) -> Vec<Position> {
     let mut pos_out = Vec::with_capacity(1000);
-    let bounds = 0..*dim as isize;
     for pos_idx_1 in 0..map.len() {
         for pos_idx_2 in (pos_idx_1 + 1)..map.len() {
             let pos_1 = map[pos_idx_1];
@@ -85,7 +71,7 @@ fn get_antinode_locs(map: &Map, dim: &usize, depth: usize, include_towers: bool)
 
             pos_out.append(&mut calculate_antinodes(
                 &(pos_1, pos_2),
-                &bounds,
+                antennas,
                 depth,
                 include_towers,
             ));
@@ -95,35 +81,65 @@ fn get_antinode_locs(map: &Map, dim: &usize, depth: usize, include_towers: bool)
 }
 
 #[aoc(day8, part1)]
-fn count_unique_antinodes((maps, dim): &(Vec<Map>, Dim)) -> u32 {
+fn count_unique_antinodes((maps, antennas): &(Vec<Map>, Antennas)) -> u32 {
     let positions = maps
         .iter()
-        .flat_map(|map| get_antinode_locs(map, dim, 1, false))
+        .flat_map(|map| get_antinode_locs(map, antennas, 1, false))
         .collect::<HashSet<Position>>();
 
     positions.len() as u32
 }
 #[aoc(day8, part2)]
-fn count_unique_antinodes_depth_2((maps, dim): &(Vec<Map>, Dim)) -> u32 {
+fn count_unique_antinodes_depth_2((maps, antennas): &(Vec<Map>, Antennas)) -> u32 {
+    let depth = antennas.rows().max(antennas.columns());
     let positions = maps
         .iter()
-        .flat_map(|map| get_antinode_locs(map, dim, *dim, true))
+        .flat_map(|map| get_antinode_locs(map, antennas, depth, true))
         .collect::<HashSet<Position>>();
 
     positions.len() as u32
 }
 
-pub fn part1(input: &str) -> u32 {
-    count_unique_antinodes(&generate_maps(input))
+pub fn part1(input: &str) -> Result<Answer, SolveError> {
+    count_unique_antinodes(&try_generate_maps(input)?).into_answer()
+}
+
+pub fn part2(input: &str) -> Result<Answer, SolveError> {
+    count_unique_antinodes_depth_2(&try_generate_maps(input)?).into_answer()
 }
 
-pub fn part2(input: &str) -> u32 {
-    count_unique_antinodes_depth_2(&generate_maps(input))
+pub struct Day8;
+
+impl Solution for Day8 {
+    const DAY: u8 = 8;
+    const VARIANTS: &'static [Variant] = &[
+        Variant::new(1, DEFAULT_VARIANT, parse_input::<Self>, |parsed| {
+            count_unique_antinodes(input(parsed)).into_answer()
+        }),
+        Variant::new(2, DEFAULT_VARIANT, parse_input::<Self>, |parsed| {
+            count_unique_antinodes_depth_2(input(parsed)).into_answer()
+        }),
+    ];
+    type Input = (Vec<Map>, Antennas);
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        try_generate_maps(input)
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(count_unique_antinodes(input) as Answer)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(count_unique_antinodes_depth_2(input) as Answer)
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{count_unique_antinodes, count_unique_antinodes_depth_2, generate_maps};
+    use super::{
+        count_unique_antinodes, count_unique_antinodes_depth_2, generate_maps, try_generate_maps,
+    };
 
     const INPUT: &str = r"............
 ........0...
@@ -139,7 +155,7 @@ mod test {
 ............";
     #[test]
     fn test_generator_d8() {
-        let (mut maps, _dim) = generate_maps(INPUT);
+        let (mut maps, _antennas) = generate_maps(INPUT);
         maps.sort(); // prevent random shuffling due to maps collect
         assert_eq!(
             maps,
@@ -150,15 +166,21 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_try_generator() {
+        let error = try_generate_maps("..a.\n.#..").unwrap_err();
+        assert_eq!((error.day, error.line, error.column), (8, 2, 2));
+    }
+
     #[test]
     fn test_count_antinodes() {
-        let (maps, dim) = generate_maps(INPUT);
-        assert_eq!(count_unique_antinodes(&(maps, dim)), 14);
+        let (maps, antennas) = generate_maps(INPUT);
+        assert_eq!(count_unique_antinodes(&(maps, antennas)), 14);
     }
 
     #[test]
     fn test_count_antinodes_2() {
-        let (maps, dim) = generate_maps(INPUT);
-        assert_eq!(count_unique_antinodes_depth_2(&(maps, dim)), 34);
+        let (maps, antennas) = generate_maps(INPUT);
+        assert_eq!(count_unique_antinodes_depth_2(&(maps, antennas)), 34);
     }
 }
@@ -1,3 +1,6 @@
+use crate::error::{ParseError, SolveError};
+use crate::input::normalise;
+use crate::solution::{input, Answer, IntoAnswer, Parsed, Solution, Variant, DEFAULT_VARIANT};
 use std::{
     collections::VecDeque,
     iter::{repeat, Repeat, Take},
@@ -25,6 +28,7 @@ impl ReadingState {
             }
         }
     }
+    #[allow(clippy::manual_repeat_n)]
     fn get_content(&self, length: u32) -> Take<Repeat<Option<usize>>> {
         repeat(match self {
             ReadingState::File { file_index } => Some(*file_index),
@@ -33,21 +37,40 @@ impl ReadingState {
         .take(length as usize)
     }
 }
+/// Lengths of the alternating file and free space blocks.
+fn read_lengths(input: &str) -> impl Iterator<Item = Result<u32, ParseError>> + '_ {
+    input.char_indices().map(|(index, char)| {
+        char.to_digit(10).ok_or_else(|| {
+            ParseError::in_line(
+                9,
+                0,
+                input,
+                &input[index..index + char.len_utf8()],
+                "expected a digit",
+            )
+        })
+    })
+}
+
 #[aoc_generator(day9, part1)]
 fn read_memory(input: &str) -> (Vec<Option<usize>>, u32) {
+    try_read_memory(input).unwrap_or_else(|error| panic!("{error}"))
+}
+
+pub fn try_read_memory(input: &str) -> Result<(Vec<Option<usize>>, u32), ParseError> {
+    let input = normalise(input);
     let mut memory: Vec<Option<usize>> = Vec::with_capacity(INPUT_LENGTH);
-    let chars = input.chars();
 
     let mut total_filled = 0;
     let mut reading_state = ReadingState::File { file_index: 0 };
-    for content_length in chars {
-        let content_length = content_length.to_digit(10).unwrap();
+    for content_length in read_lengths(&input) {
+        let content_length = content_length?;
         memory.extend(reading_state.get_content(content_length));
         total_filled += content_length;
         reading_state.switch();
     }
 
-    (memory, total_filled)
+    Ok((memory, total_filled))
 }
 
 fn calculate_checksum(memory: &[usize]) -> usize {
@@ -81,12 +104,12 @@ fn consolidate_memory((input, total_filled): &(Vec<Option<usize>>, u32)) -> usiz
 }
 
 #[derive(Debug, Clone, Copy)]
-struct FreeSpace {
+pub struct FreeSpace {
     offset: usize,
     size: u32,
 }
 #[derive(Debug, Clone, Copy)]
-struct File {
+pub struct File {
     size: u32,
     offset: usize,
     index: usize,
@@ -94,14 +117,18 @@ struct File {
 
 #[aoc_generator(day9, part2)]
 fn read_memory_to_blocks(input: &str) -> (Vec<File>, Vec<FreeSpace>) {
+    try_read_memory_to_blocks(input).unwrap_or_else(|error| panic!("{error}"))
+}
+
+pub fn try_read_memory_to_blocks(input: &str) -> Result<(Vec<File>, Vec<FreeSpace>), ParseError> {
+    let input = normalise(input);
     let mut files: Vec<File> = Vec::with_capacity(INPUT_LENGTH);
     let mut free_space: Vec<FreeSpace> = Vec::with_capacity(INPUT_LENGTH);
 
-    let chars = input.chars();
     let mut offset = 0;
     let mut reading_state = ReadingState::File { file_index: 0 };
-    for content_length in chars {
-        let content_length = content_length.to_digit(10).unwrap();
+    for content_length in read_lengths(&input) {
+        let content_length = content_length?;
         match reading_state {
             ReadingState::File { file_index } => files.push(File {
                 index: file_index,
@@ -117,7 +144,7 @@ fn read_memory_to_blocks(input: &str) -> (Vec<File>, Vec<FreeSpace>) {
         reading_state.switch();
     }
 
-    (files, free_space)
+    Ok((files, free_space))
 }
 #[aoc(day9, part2)]
 fn consolidate_memory_fit((files, free_spaces): &(Vec<File>, Vec<FreeSpace>)) -> usize {
@@ -161,18 +188,64 @@ fn consolidate_memory_fit((files, free_spaces): &(Vec<File>, Vec<FreeSpace>)) ->
     })
 }
 
-pub fn part1(input: &str) -> usize {
-    consolidate_memory(&read_memory(input))
+pub fn part1(input: &str) -> Result<Answer, SolveError> {
+    consolidate_memory(&try_read_memory(input)?).into_answer()
 }
-pub fn part2(input: &str) -> usize {
-    consolidate_memory_fit(&read_memory_to_blocks(input))
+
+pub fn part2(input: &str) -> Result<Answer, SolveError> {
+    consolidate_memory_fit(&try_read_memory_to_blocks(input)?).into_answer()
+}
+
+pub struct Day9;
+
+impl Solution for Day9 {
+    const DAY: u8 = 9;
+    // every part has its own generator
+    const VARIANTS: &'static [Variant] = &[
+        Variant::new(
+            1,
+            DEFAULT_VARIANT,
+            |raw| Ok(Box::new(try_read_memory(raw)?) as Parsed),
+            |parsed| consolidate_memory(input(parsed)).into_answer(),
+        ),
+        Variant::new(
+            2,
+            DEFAULT_VARIANT,
+            |raw| Ok(Box::new(try_read_memory_to_blocks(raw)?) as Parsed),
+            |parsed| consolidate_memory_fit(input(parsed)).into_answer(),
+        ),
+    ];
+    type Input = ((Vec<Option<usize>>, u32), (Vec<File>, Vec<FreeSpace>));
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        Ok((try_read_memory(input)?, try_read_memory_to_blocks(input)?))
+    }
+
+    fn part1(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(consolidate_memory(&input.0) as Answer)
+    }
+
+    fn part2(input: &Self::Input) -> Result<Answer, SolveError> {
+        Ok(consolidate_memory_fit(&input.1) as Answer)
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{consolidate_memory, consolidate_memory_fit, read_memory, read_memory_to_blocks};
+    use super::{
+        consolidate_memory, consolidate_memory_fit, read_memory, read_memory_to_blocks,
+        try_read_memory, try_read_memory_to_blocks,
+    };
 
     const INPUT: &str = "2333133121414131402";
+    #[test]
+    fn test_try_generators() {
+        let error = try_read_memory("23331x3").unwrap_err();
+        assert_eq!((error.day, error.line, error.column), (9, 1, 6));
+        assert!(try_read_memory_to_blocks("2333-").is_err());
+        assert_eq!(try_read_memory("12345\r\n"), try_read_memory("12345"));
+    }
+
     #[test]
     fn test_consolidate_memory() {
         assert_eq!(consolidate_memory(&read_memory(INPUT)), 1928);
@@ -0,0 +1,249 @@
+use crate::solution::{Answer, Day, Variant};
+use std::fmt::Display;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// What a variant made of an input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Answer(Answer),
+    Error(String),
+    Panic(String),
+}
+
+impl Outcome {
+    /// Answers have to match, rejecting the input is fine as long as both
+    /// variants do, no matter how.
+    pub fn agrees_with(&self, other: &Outcome) -> bool {
+        match (self, other) {
+            (Outcome::Answer(a), Outcome::Answer(b)) => a == b,
+            (Outcome::Answer(_), _) | (_, Outcome::Answer(_)) => false,
+            _ => true,
+        }
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Answer(answer) => write!(f, "{answer}"),
+            Outcome::Error(error) => write!(f, "error: {error}"),
+            Outcome::Panic(message) => write!(f, "panic: {message}"),
+        }
+    }
+}
+
+pub fn run_variant(variant: &Variant, input: &str) -> Outcome {
+    match catch_unwind(AssertUnwindSafe(|| variant.run(input))) {
+        Ok(Ok(answer)) => Outcome::Answer(answer),
+        Ok(Err(error)) => Outcome::Error(error.to_string()),
+        Err(payload) => Outcome::Panic(
+            payload
+                .downcast_ref::<String>()
+                .cloned()
+                .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+                .unwrap_or_default(),
+        ),
+    }
+}
+
+/// Two variants of the same part which do not agree on `input`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disagreement {
+    pub day: u8,
+    pub part: u8,
+    /// smallest input found which still shows the disagreement
+    pub input: String,
+    pub original_lines: usize,
+    /// every variant of the part on the minimised input
+    pub outcomes: Vec<(&'static str, Outcome)>,
+}
+
+impl Display for Disagreement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "day {} part {}: variants disagree on this input (minimised from {} lines):",
+            self.day, self.part, self.original_lines
+        )?;
+        for line in self.input.lines() {
+            writeln!(f, "    {line}")?;
+        }
+        let (reference_name, reference) = &self.outcomes[0];
+        for (name, outcome) in &self.outcomes {
+            let marker = if outcome.agrees_with(reference) {
+                ' '
+            } else {
+                '!'
+            };
+            writeln!(f, "{marker} {name:<20} {outcome}")?;
+        }
+        write!(f, "(! marks disagreement with {reference_name})")
+    }
+}
+
+fn first_conflict(variants: &[&Variant], input: &str) -> Option<(usize, usize)> {
+    let outcomes = variants
+        .iter()
+        .map(|variant| run_variant(variant, input))
+        .collect::<Vec<_>>();
+    (1..outcomes.len())
+        .find(|index| !outcomes[0].agrees_with(&outcomes[*index]))
+        .map(|index| (0, index))
+}
+
+/// Delta debugging: repeatedly drops chunks of `units` (lines or chars) as
+/// long as `still_fails` holds for what is left.
+fn minimise<'a>(
+    mut units: Vec<&'a str>,
+    separator: &str,
+    still_fails: &dyn Fn(&str) -> bool,
+) -> Vec<&'a str> {
+    let mut chunks = 2;
+    while units.len() >= 2 {
+        let chunk_size = units.len().div_ceil(chunks);
+        let reduced = (0..units.len()).step_by(chunk_size).find_map(|start| {
+            let candidate = units[..start]
+                .iter()
+                .chain(&units[(start + chunk_size).min(units.len())..])
+                .copied()
+                .collect::<Vec<_>>();
+            still_fails(&candidate.join(separator)).then_some(candidate)
+        });
+        match reduced {
+            Some(candidate) => {
+                units = candidate;
+                chunks = (chunks - 1).max(2);
+            }
+            None if chunk_size == 1 => break,
+            None => chunks = (chunks * 2).min(units.len()),
+        }
+    }
+    units
+}
+
+/// Runs every variant of every part of `day` on each input and reports the
+/// first disagreement, shrunk to the smallest input still showing it.
+pub fn check_day(day: &Day, inputs: &[&str]) -> Result<(), Box<Disagreement>> {
+    for part in 1..=2 {
+        let variants = day.variants_of(part).collect::<Vec<_>>();
+        for input in inputs {
+            let Some((a, b)) = first_conflict(&variants, input) else {
+                continue;
+            };
+            let pair = [variants[a], variants[b]];
+            let still_fails = |candidate: &str| first_conflict(&pair, candidate).is_some();
+
+            let mut minimised = minimise(input.lines().collect(), "\n", &still_fails).join("\n");
+            if minimised.lines().count() == 1 {
+                // single line puzzles (disk maps, stones, memory dumps)
+                let chars = minimised
+                    .char_indices()
+                    .map(|(index, char)| &minimised[index..index + char.len_utf8()])
+                    .collect();
+                minimised = minimise(chars, "", &still_fails).concat();
+            }
+
+            return Err(Box::new(Disagreement {
+                day: day.day,
+                part,
+                outcomes: variants
+                    .iter()
+                    .map(|variant| (variant.name, run_variant(variant, &minimised)))
+                    .collect(),
+                input: minimised,
+                original_lines: input.lines().count(),
+            }));
+        }
+    }
+    Ok(())
+}
+
+/// The puzzle examples of every day, plus a few edge cases.
+pub fn corpus(day: u8) -> Vec<String> {
+    let examples: &[&str] = match day {
+        1 => &["3   4\n4   3\n2   5\n1   3\n3   9\n3   3", "1   1", "5   1\n1   5\n3   3"],
+        2 => &[
+            "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9",
+            "48 46 47 49 51 54 56\n1 1 2 3 4 5\n1 2 3 4 5 5\n5 1 2 3 4 5\n1 4 3 2 1\n1 6 7 8 9\n1 2 3 4 3\n9 8 7 6 7\n7 10 8 10 11\n29 28 27 25 26 25 22 20",
+        ],
+        3 => &[
+            "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))",
+            "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))",
+        ],
+        4 => &["MMMSXXMASM\nMSAMXMSMSA\nAMXSXMAAMM\nMSAMASMSMX\nXMASAMXAMM\nXXAMMXXAMA\nSMSMSASXSS\nSAXAMASAAA\nMAMMMXMMMM\nMXMXAXMASX"],
+        5 => &["47|53\n97|13\n97|61\n97|47\n75|29\n61|13\n75|53\n29|13\n97|29\n53|29\n61|53\n97|53\n61|29\n47|13\n75|47\n97|75\n47|61\n75|61\n47|29\n75|13\n53|13\n\n75,47,61,53,29\n97,61,53,29,13\n75,29,13\n75,97,47,61,53\n61,13,29\n97,13,75,29,47"],
+        6 => &["....#.....\n.........#\n..........\n..#.......\n.......#..\n..........\n.#..^.....\n........#.\n#.........\n......#..."],
+        7 => &["190: 10 19\n3267: 81 40 27\n83: 17 5\n156: 15 6\n7290: 6 8 6 15\n161011: 16 10 13\n192: 17 8 14\n21037: 9 7 18 13\n292: 11 6 16 20"],
+        8 => &["............\n........0...\n.....0......\n.......0....\n....0.......\n......A.....\n............\n............\n........A...\n.........A..\n............\n............"],
+        9 => &["2333133121414131402", "12345"],
+        10 => &["89010123\n78121874\n87430965\n96549874\n45678903\n32019012\n01329801\n10456732"],
+        11 => &["125 17", "0 1 10 99 999"],
+        _ => &[],
+    };
+    examples.iter().map(|example| example.to_string()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_day, corpus, Outcome};
+    use crate::error::ParseError;
+    use crate::solution::{input, raw_input, Answer, Day, Variant, DAYS};
+
+    #[test]
+    fn test_all_variants_agree() {
+        for day in DAYS {
+            let corpus = corpus(day.day);
+            assert!(!corpus.is_empty(), "no corpus for day {}", day.day);
+            let inputs = corpus.iter().map(String::as_str).collect::<Vec<_>>();
+            if let Err(disagreement) = check_day(day, &inputs) {
+                panic!("{disagreement}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimised_disagreement() {
+        // counts lines, but the buggy one skips lines with a 7 in them
+        static BROKEN: Day = Day {
+            day: 99,
+            part1: |_| Ok(0),
+            part2: |_| Ok(0),
+            variants: &[
+                Variant::new(1, "Lines", raw_input, |raw| {
+                    Ok(input::<String>(raw).lines().count() as Answer)
+                }),
+                Variant::new(1, "Buggy", raw_input, |raw| {
+                    Ok(input::<String>(raw)
+                        .lines()
+                        .filter(|line| !line.contains('7'))
+                        .count() as Answer)
+                }),
+                Variant::new(
+                    1,
+                    "Rejecting",
+                    |_| Err(ParseError::new(99, 1, 1, "", "")),
+                    |_| Ok(0),
+                ),
+            ],
+        };
+        let input = (0..40)
+            .map(|number| format!("line {number}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let disagreement = check_day(&BROKEN, &[&input]).unwrap_err();
+        assert_eq!(disagreement.input, "7");
+        assert_eq!(disagreement.original_lines, 40);
+        assert_eq!(disagreement.outcomes[0], ("Lines", Outcome::Answer(1)));
+        assert_eq!(disagreement.outcomes[1], ("Buggy", Outcome::Answer(0)));
+        let rendered = disagreement.to_string();
+        assert!(rendered.contains("! Buggy"), "{rendered}");
+    }
+
+    #[test]
+    fn test_outcomes() {
+        let answer = Outcome::Answer(1);
+        assert!(answer.agrees_with(&Outcome::Answer(1)));
+        assert!(!answer.agrees_with(&Outcome::Panic(String::new())));
+        assert!(Outcome::Panic(String::new()).agrees_with(&Outcome::Error(String::new())));
+    }
+}
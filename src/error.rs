@@ -0,0 +1,136 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Malformed puzzle input, pointing at the offending text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    /// 1-based
+    pub line: usize,
+    /// 1-based, counted in chars
+    pub column: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+impl ParseError {
+    pub fn new(
+        day: u8,
+        line: usize,
+        column: usize,
+        text: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        ParseError {
+            day,
+            line,
+            column,
+            text: text.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Error for `text`, which has to be a slice of `line`. `line_index` is
+    /// 0-based, as handed out by `input.lines().enumerate()`.
+    pub fn in_line(
+        day: u8,
+        line_index: usize,
+        line: &str,
+        text: &str,
+        reason: impl Into<String>,
+    ) -> Self {
+        let start = text.as_ptr() as usize;
+        let column = match start.checked_sub(line.as_ptr() as usize) {
+            Some(offset) if offset + text.len() <= line.len() => line[..offset].chars().count() + 1,
+            _ => 1,
+        };
+        ParseError::new(day, line_index + 1, column, text, reason)
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "day {}, line {}, column {}: {} (found {:?})",
+            self.day, self.line, self.column, self.reason, self.text
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Why a solver produced no answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    Parse(ParseError),
+    /// the answer does not fit the integer type it is computed in
+    Overflow(String),
+}
+
+impl From<ParseError> for SolveError {
+    fn from(error: ParseError) -> Self {
+        SolveError::Parse(error)
+    }
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Parse(error) => error.fmt(f),
+            SolveError::Overflow(reason) => write!(f, "overflow: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SolveError::Parse(error) => Some(error),
+            SolveError::Overflow(_) => None,
+        }
+    }
+}
+
+/// Parses `text` (a slice of `line`) as a number.
+pub fn parse_number<T: FromStr>(
+    day: u8,
+    line_index: usize,
+    line: &str,
+    text: &str,
+) -> Result<T, ParseError> {
+    text.parse::<T>().map_err(|_| {
+        ParseError::in_line(
+            day,
+            line_index,
+            line,
+            text,
+            format!("expected a number of type {}", std::any::type_name::<T>()),
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_number, ParseError};
+
+    #[test]
+    fn test_column() {
+        let line = "12   x4";
+        let error = parse_number::<u32>(1, 2, line, &line[5..]).unwrap_err();
+        assert_eq!(
+            error,
+            ParseError::new(1, 3, 6, "x4", "expected a number of type u32")
+        );
+        assert_eq!(
+            error.to_string(),
+            "day 1, line 3, column 6: expected a number of type u32 (found \"x4\")"
+        );
+    }
+
+    #[test]
+    fn test_unrelated_text() {
+        let error = ParseError::in_line(1, 0, "abc", "other", "broken");
+        assert_eq!(error.column, 1);
+    }
+}
@@ -0,0 +1,287 @@
+use crate::error::ParseError;
+use crate::input::normalise_grid;
+use std::fmt::Display;
+use std::ops::{Index, IndexMut};
+
+/// (row, column)
+pub type Position = (usize, usize);
+/// (row delta, column delta)
+pub type Offset = (isize, isize);
+
+pub const NEIGHBOURS_4: [Offset; 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+pub const NEIGHBOURS_8: [Offset; 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+pub const DIAGONALS: [Offset; 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+/// A rectangular grid of cells, stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    rows: usize,
+    columns: usize,
+}
+
+impl<T> Grid<T> {
+    pub fn new(rows: usize, columns: usize, cells: Vec<T>) -> Self {
+        assert_eq!(
+            cells.len(),
+            rows * columns,
+            "a {rows}x{columns} grid needs {} cells",
+            rows * columns
+        );
+        Grid {
+            cells,
+            rows,
+            columns,
+        }
+    }
+
+    /// Parses one row per line of the normalised input, mapping every
+    /// character to a cell. Reports unknown characters (`map_char` returning
+    /// `None`), ragged lines and empty input.
+    pub fn try_parse(
+        day: u8,
+        input: &str,
+        mut map_char: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        let (input, rows, columns) = normalise_grid(day, input)?;
+        let mut cells = Vec::with_capacity(rows * columns);
+        for (line_index, line) in input.lines().enumerate() {
+            for (byte_index, char) in line.char_indices() {
+                let cell = map_char(char).ok_or_else(|| {
+                    ParseError::in_line(
+                        day,
+                        line_index,
+                        line,
+                        &line[byte_index..byte_index + char.len_utf8()],
+                        "unexpected character",
+                    )
+                })?;
+                cells.push(cell);
+            }
+        }
+        Ok(Grid::new(rows, columns, cells))
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// (rows, columns), like `Array2::dim`
+    pub fn dim(&self) -> (usize, usize) {
+        (self.rows, self.columns)
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        position.0 < self.rows && position.1 < self.columns
+    }
+
+    pub fn get(&self, position: Position) -> Option<&T> {
+        if self.contains(position) {
+            Some(&self.cells[position.0 * self.columns + position.1])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, position: Position) -> Option<&mut T> {
+        if self.contains(position) {
+            Some(&mut self.cells[position.0 * self.columns + position.1])
+        } else {
+            None
+        }
+    }
+
+    /// Moves `position` by `offset`, returning `None` if that leaves the grid.
+    pub fn offset(&self, position: Position, offset: Offset) -> Option<Position> {
+        let row = position.0.checked_add_signed(offset.0)?;
+        let column = position.1.checked_add_signed(offset.1)?;
+        if self.contains((row, column)) {
+            Some((row, column))
+        } else {
+            None
+        }
+    }
+
+    /// All positions reachable from `position` by one of `offsets`.
+    pub fn neighbours<'a>(
+        &'a self,
+        position: Position,
+        offsets: &'a [Offset],
+    ) -> impl Iterator<Item = Position> + 'a {
+        offsets
+            .iter()
+            .filter_map(move |offset| self.offset(position, *offset))
+    }
+
+    /// Orthogonal neighbours.
+    pub fn neighbours4(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        self.neighbours(position, &NEIGHBOURS_4)
+    }
+
+    /// Orthogonal and diagonal neighbours.
+    pub fn neighbours8(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        self.neighbours(position, &NEIGHBOURS_8)
+    }
+
+    /// Walks from `position` (exclusive) in `direction` until the border.
+    /// A `(0, 0)` direction never moves, so its ray is empty.
+    pub fn ray(
+        &self,
+        position: Position,
+        direction: Offset,
+    ) -> impl Iterator<Item = Position> + '_ {
+        let first = if direction == (0, 0) {
+            None
+        } else {
+            self.offset(position, direction)
+        };
+        std::iter::successors(first, move |position| self.offset(*position, direction))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn indexed_iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        let columns = self.columns;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(index, cell)| ((index / columns, index % columns), cell))
+    }
+
+    /// Positions of all cells matching `predicate`, row by row.
+    pub fn positions<'a>(
+        &'a self,
+        mut predicate: impl FnMut(&T) -> bool + 'a,
+    ) -> impl Iterator<Item = Position> + 'a {
+        self.indexed_iter()
+            .filter_map(move |(position, cell)| predicate(cell).then_some(position))
+    }
+
+    pub fn map<U>(&self, map_cell: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid::new(
+            self.rows,
+            self.columns,
+            self.cells.iter().map(map_cell).collect(),
+        )
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Position) -> &Self::Output {
+        self.get(position).unwrap_or_else(|| {
+            panic!(
+                "position {position:?} is outside of the {}x{} grid",
+                self.rows, self.columns
+            )
+        })
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, position: Position) -> &mut Self::Output {
+        let (rows, columns) = self.dim();
+        self.get_mut(position).unwrap_or_else(|| {
+            panic!("position {position:?} is outside of the {rows}x{columns} grid")
+        })
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (row_index, row) in self.cells.chunks(self.columns.max(1)).enumerate() {
+            if row_index > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{cell}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Grid, NEIGHBOURS_8};
+
+    const INPUT: &str = "abcd\nefgh\nijkl";
+
+    #[test]
+    fn test_parse_rectangular() {
+        let grid = Grid::try_parse(0, INPUT, Some).unwrap();
+        assert_eq!(grid.dim(), (3, 4));
+        assert_eq!(grid[(0, 3)], 'd');
+        assert_eq!(grid[(2, 0)], 'i');
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.to_string(), INPUT);
+    }
+
+    #[test]
+    fn test_try_parse() {
+        let error = Grid::try_parse(4, "XM\nX?", |ch| (ch != '?').then_some(ch)).unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (2, 2, "?"));
+
+        let error = Grid::try_parse(4, "XMAS\nXM", Some).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+        assert_eq!(error.reason, "expected 4 columns, found 2");
+
+        let grid = Grid::try_parse(4, "\u{feff}XM\r\nAS\r\n\r\n", Some).unwrap();
+        assert_eq!(grid.dim(), (2, 2));
+        assert_eq!(grid[(1, 1)], 'S');
+    }
+
+    #[test]
+    fn test_offset() {
+        let grid = Grid::try_parse(0, INPUT, Some).unwrap();
+        assert_eq!(grid.offset((0, 0), (-1, 0)), None);
+        assert_eq!(grid.offset((0, 0), (0, -1)), None);
+        assert_eq!(grid.offset((1, 1), (1, 2)), Some((2, 3)));
+        assert_eq!(grid.offset((2, 3), (0, 1)), None);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::try_parse(0, INPUT, Some).unwrap();
+        assert_eq!(
+            grid.neighbours4((0, 0)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0)]
+        );
+        assert_eq!(grid.neighbours8((0, 0)).count(), 3);
+        assert_eq!(grid.neighbours8((1, 1)).count(), NEIGHBOURS_8.len());
+    }
+
+    #[test]
+    fn test_ray() {
+        let grid = Grid::try_parse(0, INPUT, Some).unwrap();
+        assert_eq!(
+            grid.ray((0, 0), (1, 1))
+                .map(|pos| grid[pos])
+                .collect::<String>(),
+            "fk"
+        );
+        assert_eq!(grid.ray((0, 3), (0, 1)).count(), 0);
+        assert_eq!(grid.ray((1, 1), (0, 0)).count(), 0);
+        assert_eq!(
+            grid.positions(|ch| "aeiou".contains(*ch))
+                .collect::<Vec<_>>(),
+            vec![(0, 0), (1, 0), (2, 0)]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Clean-up applied to the raw puzzle input before any generator sees it, so
+//! inputs saved on Windows or by editors adding newlines parse the same.
+
+use crate::error::ParseError;
+use std::borrow::Cow;
+
+const BOM: char = '\u{feff}';
+
+/// Strips a byte order mark, turns CRLF (and lone CR) line endings into LF,
+/// removes trailing whitespace from every line and drops trailing blank
+/// lines. Borrows if there is nothing to clean up.
+pub fn normalise(input: &str) -> Cow<'_, str> {
+    let input = input.strip_prefix(BOM).unwrap_or(input);
+    let clean = !input.contains('\r')
+        && input
+            .split('\n')
+            .all(|line| line.len() == line.trim_end().len())
+        && !input.ends_with('\n');
+    if clean {
+        return Cow::Borrowed(input);
+    }
+
+    let mut normalised = input
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .split('\n')
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n");
+    normalised.truncate(normalised.trim_end().len());
+    Cow::Owned(normalised)
+}
+
+/// `normalise`, then makes sure the input is a non-empty grid with lines of
+/// equal length (counted in chars). Returns the input along with its rows and
+/// columns.
+pub fn normalise_grid(day: u8, input: &str) -> Result<(Cow<'_, str>, usize, usize), ParseError> {
+    let input = normalise(input);
+    let mut columns = None;
+    let mut rows = 0;
+    for (line_index, line) in input.lines().enumerate() {
+        let width = line.chars().count();
+        match columns {
+            None => columns = Some(width),
+            Some(columns) if columns != width => {
+                return Err(ParseError::new(
+                    day,
+                    line_index + 1,
+                    columns.min(width) + 1,
+                    line,
+                    format!("expected {columns} columns, found {width}"),
+                ))
+            }
+            _ => {}
+        }
+        rows += 1;
+    }
+    match columns {
+        Some(columns) if columns > 0 => Ok((input, rows, columns)),
+        _ => Err(ParseError::new(
+            day,
+            1,
+            1,
+            "",
+            "expected a grid, found no lines",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{normalise, normalise_grid};
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_normalise() {
+        assert!(matches!(normalise("1   2\n3   4"), Cow::Borrowed(_)));
+        assert!(matches!(normalise("\u{feff}1   2"), Cow::Borrowed("1   2")));
+        assert_eq!(normalise("1   2\r\n3   4\r\n\r\n"), "1   2\n3   4");
+        assert_eq!(normalise("47|53 \n\t\n75,47\n\n\n"), "47|53\n\n75,47");
+        assert_eq!(normalise("a\rb"), "a\nb");
+        assert_eq!(normalise("\n\n"), "");
+    }
+
+    #[test]
+    fn test_normalise_grid() {
+        let (grid, rows, columns) = normalise_grid(4, "XMAS\r\nSAMX\r\n").unwrap();
+        assert_eq!((grid.as_ref(), rows, columns), ("XMAS\nSAMX", 2, 4));
+
+        let error = normalise_grid(4, "XMAS\nSAM\nXMAS").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 4));
+        assert_eq!(error.reason, "expected 4 columns, found 3");
+
+        let error = normalise_grid(10, "\r\n").unwrap_err();
+        assert_eq!(error.reason, "expected a grid, found no lines");
+    }
+}
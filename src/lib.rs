@@ -3,6 +3,9 @@ extern crate aoc_runner;
 #[macro_use]
 extern crate aoc_runner_derive;
 
+pub mod answers;
+pub mod bench;
+pub mod cli;
 pub mod day1;
 pub mod day10;
 pub mod day11;
@@ -14,5 +17,12 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+pub mod equivalence;
+pub mod error;
+pub mod grid;
+pub mod input;
+pub mod profile;
+pub mod random;
+pub mod solution;
 
 aoc_lib! { year = 2024 }
@@ -1,7 +1,13 @@
 extern crate advent_of_code_2024;
-extern crate aoc_runner;
-extern crate aoc_runner_derive;
 
-use aoc_runner_derive::aoc_main;
+use advent_of_code_2024::cli;
+use std::process::ExitCode;
 
-aoc_main! { lib = advent_of_code_2024 }
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: advent_of_code_2024::profile::CountingAllocator =
+    advent_of_code_2024::profile::CountingAllocator;
+
+fn main() -> ExitCode {
+    cli::main(std::env::args().skip(1))
+}
@@ -0,0 +1,226 @@
+//! Where time and memory go: wall time, allocations and peak heap usage of
+//! the parse and solve phase of every variant. Allocations are only counted
+//! with `CountingAllocator` installed as the global allocator, which the
+//! binary does with the `count-allocations` feature.
+
+use crate::bench::format_duration;
+use crate::error::SolveError;
+use crate::solution::{Answer, Day, Variant};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static CURRENT_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator, counting allocations and live bytes on the way.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn grow(size: usize) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        let current = CURRENT_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK_BYTES.fetch_max(current, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if !ACTIVE.load(Ordering::Relaxed) {
+            ACTIVE.store(true, Ordering::Relaxed);
+        }
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            CURRENT_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            Self::grow(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Whether `CountingAllocator` is the global allocator.
+pub fn counting_allocations() -> bool {
+    // make sure at least one allocation went through it
+    drop(Box::new(0u8));
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Phase {
+    pub wall: Duration,
+    /// allocations, including reallocations
+    pub allocations: u64,
+    /// most bytes allocated at once during the phase, on top of what was
+    /// allocated before it started
+    pub peak_bytes: usize,
+}
+
+/// Runs `f`, measuring it. The counters are global, so allocations of other
+/// threads (like rayon's workers) are included.
+pub fn measure<R>(f: impl FnOnce() -> R) -> (R, Phase) {
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let before = CURRENT_BYTES.load(Ordering::Relaxed);
+    PEAK_BYTES.store(before, Ordering::Relaxed);
+
+    let start = Instant::now();
+    let result = f();
+    let wall = start.elapsed();
+
+    let phase = Phase {
+        wall,
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        peak_bytes: PEAK_BYTES.load(Ordering::Relaxed).saturating_sub(before),
+    };
+    (result, phase)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub day: u8,
+    pub part: u8,
+    pub variant: &'static str,
+    pub answer: Answer,
+    pub parse: Phase,
+    pub solve: Phase,
+}
+
+pub fn profile_variant(day: u8, variant: &Variant, input: &str) -> Result<Profile, SolveError> {
+    let (parsed, parse) = measure(|| (variant.parse)(input));
+    let parsed = parsed?;
+    let (answer, solve) = measure(|| (variant.solve)(&parsed));
+    Ok(Profile {
+        day,
+        part: variant.part,
+        variant: variant.name,
+        answer: answer?,
+        parse,
+        solve,
+    })
+}
+
+/// Profiles every variant of `day`, optionally only of one part or with one
+/// name.
+pub fn profile_day(
+    day: &Day,
+    part: Option<u8>,
+    name: Option<&str>,
+    input: &str,
+) -> Result<Vec<Profile>, SolveError> {
+    day.variants
+        .iter()
+        .filter(|variant| part.is_none_or(|part| variant.part == part))
+        .filter(|variant| name.is_none_or(|name| variant.name.eq_ignore_ascii_case(name)))
+        .map(|variant| profile_variant(day.day, variant, input))
+        .collect()
+}
+
+fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        0..1024 => format!("{bytes}B"),
+        1024..1_048_576 => format!("{:.1}KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1}MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+pub fn render(profiles: &[Profile], counting: bool) -> String {
+    let mut lines = vec![format!(
+        "{:<8}{:<20}{:>12}{:>10}{:>10}{:>12}{:>10}{:>10}  answer",
+        "part", "variant", "parse", "allocs", "peak", "solve", "allocs", "peak"
+    )];
+    let allocations = |phase: &Phase| {
+        if counting {
+            (
+                phase.allocations.to_string(),
+                format_bytes(phase.peak_bytes),
+            )
+        } else {
+            ("-".to_string(), "-".to_string())
+        }
+    };
+    for profile in profiles {
+        let (parse_allocations, parse_peak) = allocations(&profile.parse);
+        let (solve_allocations, solve_peak) = allocations(&profile.solve);
+        lines.push(format!(
+            "{:<8}{:<20}{:>12}{:>10}{:>10}{:>12}{:>10}{:>10}  {}",
+            format!("{}/{}", profile.day, profile.part),
+            profile.variant,
+            format_duration(profile.parse.wall),
+            parse_allocations,
+            parse_peak,
+            format_duration(profile.solve.wall),
+            solve_allocations,
+            solve_peak,
+            profile.answer
+        ));
+    }
+    if !counting {
+        lines.push("allocations are not counted, build with --features count-allocations".into());
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{counting_allocations, format_bytes, measure, profile_day};
+    use crate::solution::find_day;
+
+    #[cfg(feature = "count-allocations")]
+    #[global_allocator]
+    static ALLOCATOR: super::CountingAllocator = super::CountingAllocator;
+
+    #[test]
+    fn test_measure() {
+        let (values, phase) = measure(|| vec![0u64; 1000]);
+        assert_eq!(values.len(), 1000);
+        if counting_allocations() {
+            assert!(phase.allocations >= 1);
+        } else {
+            assert_eq!(phase.allocations, 0);
+        }
+    }
+
+    #[test]
+    fn test_profile_day() {
+        let sample = "7 6 4 2 1\n1 2 7 8 9\n9 7 6 2 1\n1 3 2 4 5\n8 6 4 4 1\n1 3 6 7 9";
+        let day2 = find_day(2).unwrap();
+        let profiles = profile_day(day2, Some(2), None, sample).unwrap();
+        assert_eq!(profiles.len(), day2.variants_of(2).count());
+        assert!(profiles.iter().all(|profile| profile.answer == 4));
+        if counting_allocations() {
+            // every variant has to at least build the reports
+            assert!(profiles
+                .iter()
+                .all(|profile| profile.parse.allocations + profile.solve.allocations > 0));
+        }
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(12), "12B");
+        assert_eq!(format_bytes(2048), "2.0KiB");
+        assert_eq!(format_bytes(3 << 20), "3.0MiB");
+    }
+}
@@ -0,0 +1,600 @@
+//! Seeded generators for puzzle inputs far larger (or weirder) than the
+//! examples. Every generator produces input its day's parser accepts.
+
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+/// splitmix64, small and good enough for test data.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`, `bound` must not be 0.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        // the modulo bias is irrelevant for test data
+        self.next_u64() % bound
+    }
+
+    pub fn range(&mut self, range: RangeInclusive<u64>) -> u64 {
+        let (start, end) = range.into_inner();
+        start + self.below(end - start + 1)
+    }
+
+    pub fn index(&mut self, len: usize) -> usize {
+        self.below(len as u64) as usize
+    }
+
+    /// True with `probability` (0.0 to 1.0).
+    pub fn chance(&mut self, probability: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < probability
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.index(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            items.swap(index, self.index(index + 1));
+        }
+    }
+}
+
+fn join_lines(lines: impl Iterator<Item = String>) -> String {
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocationListOptions {
+    pub lines: usize,
+    pub max_id: u64,
+    /// probability of reusing an id seen before, to exercise part 2
+    pub repeat: f64,
+}
+
+impl Default for LocationListOptions {
+    fn default() -> Self {
+        LocationListOptions {
+            lines: 100,
+            max_id: 99_999,
+            repeat: 0.3,
+        }
+    }
+}
+
+pub fn location_lists(rng: &mut Rng, options: &LocationListOptions) -> String {
+    let mut seen = Vec::new();
+    let mut id = |rng: &mut Rng| {
+        if !seen.is_empty() && rng.chance(options.repeat) {
+            *rng.choose(&seen)
+        } else {
+            let id = rng.range(1..=options.max_id);
+            seen.push(id);
+            id
+        }
+    };
+    join_lines((0..options.lines).map(|_| format!("{}   {}", id(rng), id(rng))))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportOptions {
+    pub reports: usize,
+    pub levels: RangeInclusive<usize>,
+    pub max_level: u64,
+    /// probability of each level breaking the gradual in- or decrease
+    pub fault: f64,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        ReportOptions {
+            reports: 100,
+            levels: 5..=8,
+            max_level: 99,
+            fault: 0.05,
+        }
+    }
+}
+
+pub fn reports(rng: &mut Rng, options: &ReportOptions) -> String {
+    join_lines((0..options.reports).map(|_| {
+        let length = rng.range(*options.levels.start() as u64..=*options.levels.end() as u64);
+        let increasing = rng.chance(0.5);
+        let mut level = rng.range(0..=options.max_level) as i64;
+        let mut levels = Vec::new();
+        for _ in 0..length {
+            levels.push(level.to_string());
+            let step = if rng.chance(options.fault) {
+                rng.range(0..=6) as i64 - 3
+            } else {
+                rng.range(1..=3) as i64
+            };
+            level = if increasing {
+                level + step
+            } else {
+                level - step
+            };
+            // bounce off the edges instead of leaving the allowed levels
+            level = level.clamp(0, options.max_level as i64);
+        }
+        levels.join(" ")
+    }))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryOptions {
+    pub lines: usize,
+    pub tokens_per_line: usize,
+    /// probability of a token being a valid `mul(a,b)`
+    pub valid: f64,
+    /// probability of a token being `do()` or `don't()`
+    pub toggles: f64,
+}
+
+impl Default for MemoryOptions {
+    fn default() -> Self {
+        MemoryOptions {
+            lines: 3,
+            tokens_per_line: 200,
+            valid: 0.2,
+            toggles: 0.05,
+        }
+    }
+}
+
+pub fn memory(rng: &mut Rng, options: &MemoryOptions) -> String {
+    const NOISE: &[&str] = &[
+        "mul",
+        "mul(",
+        "mul(1",
+        "mul(12,",
+        "mul(4*",
+        "mul[3,7]",
+        "mul ( 2 , 4 )",
+        "don't",
+        "do(",
+        "select()",
+        "when()",
+        "(",
+        ")",
+        ",",
+        "#",
+        "'",
+        "]",
+        "%",
+        "&",
+        "^",
+        "from()",
+        "who()",
+        "mul(1234,5)",
+        "+",
+        "?",
+    ];
+    join_lines((0..options.lines).map(|_| {
+        (0..options.tokens_per_line)
+            .map(|_| {
+                if rng.chance(options.valid) {
+                    format!("mul({},{})", rng.range(0..=999), rng.range(0..=999))
+                } else if rng.chance(options.toggles) {
+                    rng.choose(&["do()", "don't()"]).to_string()
+                } else {
+                    rng.choose(NOISE).to_string()
+                }
+            })
+            .collect::<String>()
+    }))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordGridOptions {
+    pub rows: usize,
+    pub columns: usize,
+    pub alphabet: String,
+}
+
+impl Default for WordGridOptions {
+    fn default() -> Self {
+        WordGridOptions {
+            rows: 40,
+            columns: 40,
+            alphabet: "XMAS".to_string(),
+        }
+    }
+}
+
+pub fn word_grid(rng: &mut Rng, options: &WordGridOptions) -> String {
+    let alphabet = options.alphabet.chars().collect::<Vec<_>>();
+    join_lines((0..options.rows).map(|_| {
+        (0..options.columns)
+            .map(|_| *rng.choose(&alphabet))
+            .collect()
+    }))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManualOptions {
+    /// distinct page numbers, at most 90 as they are two digits
+    pub pages: usize,
+    /// probability of a rule between any two pages, the puzzle orders every
+    /// pair and only CheckRules and FilterByChecking cope with gaps
+    pub rule_density: f64,
+    pub updates: usize,
+    /// odd lengths only, the middle page is summed
+    pub update_length: RangeInclusive<usize>,
+}
+
+impl Default for ManualOptions {
+    fn default() -> Self {
+        ManualOptions {
+            pages: 30,
+            rule_density: 1.0,
+            updates: 50,
+            update_length: 3..=11,
+        }
+    }
+}
+
+/// Rules follow one hidden total order, so they never contradict each other.
+pub fn manual(rng: &mut Rng, options: &ManualOptions) -> String {
+    let mut pages = (10..100).collect::<Vec<u64>>();
+    rng.shuffle(&mut pages);
+    pages.truncate(options.pages.clamp(1, 90));
+
+    let mut rules = Vec::new();
+    for (index, before) in pages.iter().enumerate() {
+        for after in &pages[index + 1..] {
+            if rng.chance(options.rule_density) {
+                rules.push(format!("{before}|{after}"));
+            }
+        }
+    }
+    rng.shuffle(&mut rules);
+
+    let updates = (0..options.updates).map(|_| {
+        let (start, end) = (*options.update_length.start(), *options.update_length.end());
+        let length = (rng.range(start as u64..=end as u64) as usize | 1).min(pages.len());
+        let mut update = pages.clone();
+        rng.shuffle(&mut update);
+        update.truncate(length);
+        update
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    });
+    format!("{}\n\n{}", rules.join("\n"), join_lines(updates))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuardMapOptions {
+    pub rows: usize,
+    pub columns: usize,
+    pub obstructions: f64,
+    /// allow maps on which the guard never leaves, part 1 panics on those
+    pub allow_loops: bool,
+}
+
+impl Default for GuardMapOptions {
+    fn default() -> Self {
+        GuardMapOptions {
+            rows: 30,
+            columns: 30,
+            obstructions: 0.1,
+            allow_loops: false,
+        }
+    }
+}
+
+fn guard_leaves(map: &[Vec<char>], mut position: (usize, usize)) -> bool {
+    let mut direction = (-1, 0);
+    let mut visited = HashSet::new();
+    while visited.insert((position, direction)) {
+        let next = (
+            position.0.checked_add_signed(direction.0),
+            position.1.checked_add_signed(direction.1),
+        );
+        let (Some(row), Some(column)) = next else {
+            return true;
+        };
+        match map.get(row).and_then(|line| line.get(column)) {
+            None => return true,
+            Some('#') => direction = (direction.1, -direction.0),
+            Some(_) => position = (row, column),
+        }
+    }
+    false
+}
+
+pub fn guard_map(rng: &mut Rng, options: &GuardMapOptions) -> String {
+    let (rows, columns) = (options.rows.max(1), options.columns.max(1));
+    loop {
+        let mut map = (0..rows)
+            .map(|_| {
+                (0..columns)
+                    .map(|_| {
+                        if rng.chance(options.obstructions) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let guard = (rng.index(rows), rng.index(columns));
+        map[guard.0][guard.1] = '^';
+        if options.allow_loops || guard_leaves(&map, guard) {
+            return join_lines(map.into_iter().map(|line| line.into_iter().collect()));
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquationOptions {
+    pub equations: usize,
+    pub operands: RangeInclusive<usize>,
+    pub max_operand: u64,
+    /// probability of the test value being reachable with `+`, `*` and `||`
+    pub solvable: f64,
+}
+
+impl Default for EquationOptions {
+    fn default() -> Self {
+        EquationOptions {
+            equations: 50,
+            operands: 2..=6,
+            max_operand: 99,
+            solvable: 0.5,
+        }
+    }
+}
+
+pub fn equations(rng: &mut Rng, options: &EquationOptions) -> String {
+    join_lines((0..options.equations).map(|_| {
+        let (start, end) = (*options.operands.start(), *options.operands.end());
+        let count = rng.range(start.max(1) as u64..=end.max(1) as u64);
+        let operands = (0..count)
+            .map(|_| rng.range(1..=options.max_operand))
+            .collect::<Vec<_>>();
+        let target = if rng.chance(options.solvable) {
+            operands[1..]
+                .iter()
+                .fold(operands[0], |total, operand| match rng.below(3) {
+                    0 => total.saturating_add(*operand),
+                    1 => total.saturating_mul(*operand),
+                    _ => format!("{total}{operand}").parse().unwrap_or(u64::MAX),
+                })
+        } else {
+            rng.range(1..=operands.iter().product::<u64>().clamp(1, u32::MAX as u64))
+        };
+        let operands = operands
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("{target}: {operands}")
+    }))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AntennaMapOptions {
+    pub rows: usize,
+    pub columns: usize,
+    pub frequencies: usize,
+    pub antennas_per_frequency: RangeInclusive<usize>,
+}
+
+impl Default for AntennaMapOptions {
+    fn default() -> Self {
+        AntennaMapOptions {
+            rows: 30,
+            columns: 30,
+            frequencies: 5,
+            antennas_per_frequency: 2..=5,
+        }
+    }
+}
+
+pub fn antenna_map(rng: &mut Rng, options: &AntennaMapOptions) -> String {
+    const FREQUENCIES: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    let (rows, columns) = (options.rows.max(1), options.columns.max(1));
+    let mut map = vec![vec!['.'; columns]; rows];
+    for frequency in FREQUENCIES.chars().take(options.frequencies) {
+        let (start, end) = (
+            *options.antennas_per_frequency.start(),
+            *options.antennas_per_frequency.end(),
+        );
+        for _ in 0..rng.range(start as u64..=end as u64) {
+            map[rng.index(rows)][rng.index(columns)] = frequency;
+        }
+    }
+    join_lines(map.into_iter().map(|line| line.into_iter().collect()))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskMapOptions {
+    pub files: usize,
+    /// probability of a free space being empty
+    pub no_gap: f64,
+}
+
+impl Default for DiskMapOptions {
+    fn default() -> Self {
+        DiskMapOptions {
+            files: 200,
+            no_gap: 0.1,
+        }
+    }
+}
+
+pub fn disk_map(rng: &mut Rng, options: &DiskMapOptions) -> String {
+    let mut digits = String::new();
+    for file in 0..options.files.max(1) {
+        if file > 0 {
+            let gap = if rng.chance(options.no_gap) {
+                0
+            } else {
+                rng.range(1..=9)
+            };
+            digits.push_str(&gap.to_string());
+        }
+        digits.push_str(&rng.range(1..=9).to_string());
+    }
+    digits
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopographyOptions {
+    pub rows: usize,
+    pub columns: usize,
+    /// probability of a height not being one step away from its neighbour,
+    /// lower values give longer trails
+    pub roughness: f64,
+}
+
+impl Default for TopographyOptions {
+    fn default() -> Self {
+        TopographyOptions {
+            rows: 30,
+            columns: 30,
+            roughness: 0.2,
+        }
+    }
+}
+
+pub fn topography(rng: &mut Rng, options: &TopographyOptions) -> String {
+    let (rows, columns) = (options.rows.max(1), options.columns.max(1));
+    let mut heights = vec![vec![0u64; columns]; rows];
+    for row in 0..rows {
+        for column in 0..columns {
+            let neighbour = match (row, column) {
+                (0, 0) => None,
+                (0, _) => Some(heights[row][column - 1]),
+                (_, 0) => Some(heights[row - 1][column]),
+                _ => Some(*rng.choose(&[heights[row - 1][column], heights[row][column - 1]])),
+            };
+            heights[row][column] = match neighbour {
+                Some(height) if !rng.chance(options.roughness) => {
+                    if height == 0 || (height < 9 && rng.chance(0.5)) {
+                        height + 1
+                    } else {
+                        height - 1
+                    }
+                }
+                _ => rng.range(0..=9),
+            };
+        }
+    }
+    join_lines(heights.into_iter().map(|line| {
+        line.into_iter()
+            .map(|height| char::from(b'0' + height as u8))
+            .collect()
+    }))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoneOptions {
+    pub stones: usize,
+    pub max_digits: u32,
+}
+
+impl Default for StoneOptions {
+    fn default() -> Self {
+        StoneOptions {
+            stones: 8,
+            max_digits: 7,
+        }
+    }
+}
+
+pub fn stones(rng: &mut Rng, options: &StoneOptions) -> String {
+    let max = 10u64.pow(options.max_digits.clamp(1, 12)) - 1;
+    (0..options.stones.max(1))
+        .map(|_| rng.range(0..=max).to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Input for `day` with the default options.
+pub fn generate(day: u8, rng: &mut Rng) -> Option<String> {
+    Some(match day {
+        1 => location_lists(rng, &Default::default()),
+        2 => reports(rng, &Default::default()),
+        3 => memory(rng, &Default::default()),
+        4 => word_grid(rng, &Default::default()),
+        5 => manual(rng, &Default::default()),
+        6 => guard_map(rng, &Default::default()),
+        7 => equations(rng, &Default::default()),
+        8 => antenna_map(rng, &Default::default()),
+        9 => disk_map(rng, &Default::default()),
+        10 => topography(rng, &Default::default()),
+        11 => stones(rng, &Default::default()),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{generate, manual, ManualOptions, Rng};
+    use crate::equivalence::check_day;
+    use crate::solution::DAYS;
+
+    #[test]
+    fn test_rng() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        assert_eq!(
+            (0..10).map(|_| a.next_u64()).collect::<Vec<_>>(),
+            (0..10).map(|_| b.next_u64()).collect::<Vec<_>>()
+        );
+        assert!((0..1000).all(|_| (3..=5).contains(&a.range(3..=5))));
+        assert_ne!(generate(1, &mut Rng::new(1)), generate(1, &mut Rng::new(2)));
+        assert_eq!(generate(12, &mut a), None);
+    }
+
+    #[test]
+    fn test_manual() {
+        let options = ManualOptions {
+            pages: 5,
+            rule_density: 1.0,
+            updates: 3,
+            update_length: 4..=4,
+        };
+        let manual = manual(&mut Rng::new(3), &options);
+        let (rules, updates) = manual.split_once("\n\n").unwrap();
+        assert_eq!(rules.lines().count(), 10);
+        assert!(updates.lines().all(|update| update.split(',').count() == 5));
+    }
+
+    #[test]
+    fn test_generated_inputs() {
+        for seed in 0..3 {
+            let mut rng = Rng::new(seed);
+            for day in DAYS {
+                let input = generate(day.day, &mut rng).unwrap();
+                for variant in day.variants {
+                    if let Err(error) = (variant.parse)(&input) {
+                        panic!("day {} seed {seed}: {error}\n{input}", day.day);
+                    }
+                }
+                if let Err(disagreement) = check_day(day, &[&input]) {
+                    panic!("seed {seed}: {disagreement}");
+                }
+            }
+        }
+    }
+}
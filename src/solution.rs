@@ -0,0 +1,233 @@
+use crate::error::{ParseError, SolveError};
+use crate::{day1, day10, day11, day2, day3, day4, day5, day6, day7, day8, day9};
+use std::any::Any;
+
+pub type Answer = u64;
+/// Output of a variant's parser, handed to its solver.
+pub type Parsed = Box<dyn Any>;
+/// Parses and solves one part.
+pub type PartSolver = fn(&str) -> Result<Answer, SolveError>;
+
+/// Name of the variants registered by `#[aoc]` without a name.
+pub const DEFAULT_VARIANT: &str = "default";
+
+/// A puzzle day: one parser shared by both parts, the two canonical solvers
+/// and every `#[aoc]` implementation as a named variant.
+pub trait Solution {
+    const DAY: u8;
+    const VARIANTS: &'static [Variant];
+    type Input: 'static;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError>;
+    fn part1(input: &Self::Input) -> Result<Answer, SolveError>;
+    fn part2(input: &Self::Input) -> Result<Answer, SolveError>;
+
+    fn solve(input: &str) -> Result<(Answer, Answer), SolveError> {
+        let input = Self::parse(input)?;
+        Ok((Self::part1(&input)?, Self::part2(&input)?))
+    }
+}
+
+/// One `#[aoc(dayN, partN, Name)]` implementation, with its parsing step
+/// separated from solving.
+#[derive(Debug, Clone, Copy)]
+pub struct Variant {
+    pub part: u8,
+    pub name: &'static str,
+    pub parse: fn(&str) -> Result<Parsed, ParseError>,
+    pub solve: fn(&Parsed) -> Result<Answer, SolveError>,
+}
+
+impl Variant {
+    pub const fn new(
+        part: u8,
+        name: &'static str,
+        parse: fn(&str) -> Result<Parsed, ParseError>,
+        solve: fn(&Parsed) -> Result<Answer, SolveError>,
+    ) -> Self {
+        Variant {
+            part,
+            name,
+            parse,
+            solve,
+        }
+    }
+
+    pub fn run(&self, input: &str) -> Result<Answer, SolveError> {
+        (self.solve)(&(self.parse)(input)?)
+    }
+}
+
+/// Converts what a solver returns into an `Answer`.
+pub trait IntoAnswer {
+    fn into_answer(self) -> Result<Answer, SolveError>;
+}
+
+impl IntoAnswer for u64 {
+    fn into_answer(self) -> Result<Answer, SolveError> {
+        Ok(self)
+    }
+}
+
+impl IntoAnswer for u32 {
+    fn into_answer(self) -> Result<Answer, SolveError> {
+        Ok(self.into())
+    }
+}
+
+impl IntoAnswer for usize {
+    fn into_answer(self) -> Result<Answer, SolveError> {
+        Ok(self as Answer)
+    }
+}
+
+impl<T: IntoAnswer, E: Into<SolveError>> IntoAnswer for Result<T, E> {
+    fn into_answer(self) -> Result<Answer, SolveError> {
+        self.map_err(Into::into)?.into_answer()
+    }
+}
+
+/// Parser for variants working on `S::Input`.
+pub fn parse_input<S: Solution>(input: &str) -> Result<Parsed, ParseError> {
+    Ok(Box::new(S::parse(input)?))
+}
+
+/// Parser for variants working on the raw `&str`, they get a `String`.
+pub fn raw_input(input: &str) -> Result<Parsed, ParseError> {
+    Ok(Box::new(input.to_owned()))
+}
+
+/// Gets the concrete input back in a variant's solver.
+pub fn input<T: 'static>(parsed: &Parsed) -> &T {
+    parsed.downcast_ref::<T>().unwrap_or_else(|| {
+        panic!(
+            "variant was handed the wrong input, expected {}",
+            std::any::type_name::<T>()
+        )
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Day {
+    pub day: u8,
+    /// the day module's `part1` export
+    pub part1: PartSolver,
+    /// the day module's `part2` export
+    pub part2: PartSolver,
+    pub variants: &'static [Variant],
+}
+
+impl Day {
+    const fn of<S: Solution>(part1: PartSolver, part2: PartSolver) -> Self {
+        Day {
+            day: S::DAY,
+            part1,
+            part2,
+            variants: S::VARIANTS,
+        }
+    }
+
+    pub fn part(&self, part: u8) -> Option<PartSolver> {
+        match part {
+            1 => Some(self.part1),
+            2 => Some(self.part2),
+            _ => None,
+        }
+    }
+
+    pub fn variants_of(&self, part: u8) -> impl Iterator<Item = &'static Variant> {
+        self.variants
+            .iter()
+            .filter(move |variant| variant.part == part)
+    }
+
+    pub fn variant(&self, part: u8, name: &str) -> Option<&'static Variant> {
+        self.variants_of(part)
+            .find(|variant| variant.name.eq_ignore_ascii_case(name))
+    }
+}
+
+pub static DAYS: &[Day] = &[
+    Day::of::<day1::Day1>(day1::part1, day1::part2),
+    Day::of::<day2::Day2>(day2::part1, day2::part2),
+    Day::of::<day3::Day3>(day3::part1, day3::part2),
+    Day::of::<day4::Day4>(day4::part1, day4::part2),
+    Day::of::<day5::Day5>(day5::part1, day5::part2),
+    Day::of::<day6::Day6>(day6::part1, day6::part2),
+    Day::of::<day7::Day7>(day7::part1, day7::part2),
+    Day::of::<day8::Day8>(day8::part1, day8::part2),
+    Day::of::<day9::Day9>(day9::part1, day9::part2),
+    Day::of::<day10::Day10>(day10::part1, day10::part2),
+    Day::of::<day11::Day11>(day11::part1, day11::part2),
+];
+
+pub fn find_day(day: u8) -> Option<&'static Day> {
+    DAYS.iter().find(|entry| entry.day == day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_day, Solution, DAYS, DEFAULT_VARIANT};
+    use crate::{day1::Day1, day5::Day5};
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_registry_complete() {
+        assert_eq!(
+            DAYS.iter().map(|day| day.day).collect::<Vec<_>>(),
+            (1..=11).collect::<Vec<_>>()
+        );
+        for day in DAYS {
+            for part in 1..=2 {
+                let names = day
+                    .variants_of(part)
+                    .map(|variant| variant.name)
+                    .collect::<Vec<_>>();
+                assert!(!names.is_empty(), "day {} part {part}", day.day);
+                assert_eq!(
+                    names.iter().collect::<HashSet<_>>().len(),
+                    names.len(),
+                    "duplicate variant name on day {} part {part}",
+                    day.day
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_variants_agree() {
+        let day1 = find_day(1).unwrap();
+        let sample = "3   4\n4   3\n2   5\n1   3\n3   9\n3   3";
+        assert_eq!(Day1::solve(sample), Ok((11, 31)));
+        assert_eq!((day1.part1)(sample), Ok(11));
+        for variant in day1.variants {
+            let expected = if variant.part == 1 { 11 } else { 31 };
+            assert_eq!(variant.run(sample), Ok(expected), "{}", variant.name);
+        }
+        assert!(day1.variant(2, DEFAULT_VARIANT).is_some());
+        assert!(day1.variant(1, "parsed").is_some());
+        assert!(day1.variant(3, "parsed").is_none());
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(Day5::solve("47|53\n\n75,x").is_err());
+        let day5 = find_day(5).unwrap();
+        assert!(day5.variant(1, "CheckRules").unwrap().run("47|").is_err());
+        // any text is valid memory on day 3
+        for day in DAYS.iter().filter(|day| day.day != 3) {
+            for part in 1..=2 {
+                let solve = day.part(part).unwrap();
+                assert!(solve("1 x\n?").is_err(), "day {} part{part}", day.day);
+            }
+            for variant in day.variants {
+                assert!(
+                    variant.run("1 x\n?").is_err(),
+                    "day {} {}",
+                    day.day,
+                    variant.name
+                );
+            }
+        }
+    }
+}